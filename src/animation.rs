@@ -0,0 +1,44 @@
+use std::f32::consts::PI;
+
+// Where and how to draw an entity that steps from cell to cell. The game logic only knows whole
+// cells, the sprite slides from the previous cell to the current one while the step timer runs.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Pose
+{
+    // position in cells, fractional while walking
+    pub x: f32,
+    pub y: f32,
+    // frame of the walk cycle, 0 when standing still
+    pub frame: usize,
+    // how far the sprite is lifted off the floor, in cells
+    pub bob: f32
+}
+
+// share of the current step that is done, from 0.0 right after the step to 1.0 when the next one is due
+pub fn step_progress(time_until_next_step: f32, time_for_step: f32) -> f32
+{
+    if time_for_step <= 0.0
+    {
+        return 1.0;
+    }
+    (1.0 - time_until_next_step / time_for_step).clamp(0.0, 1.0)
+}
+
+// Every cell is half of a walk cycle, which cycle half is decided by the parity of the cell,
+// so the frames keep running from one step to the next without a counter.
+pub fn pose(previous: (usize, usize), current: (usize, usize), progress: f32, frame_count: usize) -> Pose
+{
+    let progress = progress.clamp(0.0, 1.0);
+    let x = previous.0 as f32 + (current.0 as f32 - previous.0 as f32) * progress;
+    let y = previous.1 as f32 + (current.1 as f32 - previous.1 as f32) * progress;
+    if previous == current || progress >= 1.0
+    {
+        return Pose{x: x, y: y, frame: 0, bob: 0.0};
+    }
+
+    // 0.0 to 2.0 over two steps
+    let phase = ((current.0 + current.1) % 2) as f32 + progress;
+    let frame = if frame_count > 1 { ((phase / 2.0 * frame_count as f32) as usize) % frame_count } else { 0 };
+    let bob = (phase * PI).sin().abs() * 0.06;
+    Pose{x: x, y: y, frame: frame, bob: bob}
+}
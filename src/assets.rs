@@ -1,5 +1,6 @@
-use ggez::graphics;
+use ggez::{graphics, filesystem};
 use ggez::{Context, GameResult};
+use crate::direction::Direction;
 
 const BOT_UP_IMG: &str = "\\bot_w.png";
 const BOT_DOWN_IMG: &str = "\\bot_s.png";
@@ -13,6 +14,55 @@ const FLOOR_IMG: &str = "\\floor.png";
 const WALL_IMG: &str = "\\wall.png";
 const DOOR_IMG: &str = "\\door.png";
 const KEY_IMG: &str = "\\key.png";
+// extra walk cycle frames are named after the standing sprite, e.g. `player_w_1.png`, `player_w_2.png`
+const MAX_WALK_FRAMES: usize = 8;
+// the standing sprites that have a walk cycle
+pub const WALKING_SPRITES: [&str; 8] = [PLAYER_UP_IMG, PLAYER_DOWN_IMG, PLAYER_LEFT_IMG, PLAYER_RIGHT_IMG, BOT_UP_IMG, BOT_DOWN_IMG, BOT_LEFT_IMG, BOT_RIGHT_IMG];
+
+// the frames of a walk cycle facing each way, the first one is the standing sprite
+pub struct WalkCycle {
+    pub up: Vec<graphics::Image>,
+    pub down: Vec<graphics::Image>,
+    pub left: Vec<graphics::Image>,
+    pub right: Vec<graphics::Image>
+}
+
+impl WalkCycle {
+    pub fn frames(&self, direction: Direction) -> &[graphics::Image] {
+        match direction
+        {
+            Direction::Up => &self.up,
+            Direction::Down => &self.down,
+            Direction::Left => &self.left,
+            Direction::Right => &self.right
+        }
+    }
+}
+
+// the paths of the extra frames after the standing sprite, up to the first one that is missing
+pub fn walk_frame_paths<F: Fn(&str) -> bool>(standing_path: &str, exists: F) -> Vec<String> {
+    let name = standing_path.trim_end_matches(".png");
+    let mut paths = Vec::new();
+    for index in 1..MAX_WALK_FRAMES
+    {
+        let path = format!("{}_{}.png", name, index);
+        if !exists(&path)
+        {
+            break;
+        }
+        paths.push(path);
+    }
+    paths
+}
+
+fn load_walk_frames(ctx: &mut Context, standing: &graphics::Image, standing_path: &str) -> GameResult<Vec<graphics::Image>> {
+    let mut frames = vec![standing.clone()];
+    for path in walk_frame_paths(standing_path, |path| filesystem::exists(ctx, path))
+    {
+        frames.push(graphics::Image::new(ctx, &path)?);
+    }
+    Ok(frames)
+}
 
 pub struct Assets {
     pub player_up:   graphics::Image,
@@ -26,7 +76,9 @@ pub struct Assets {
     pub floor:   graphics::Image,
     pub wall:   graphics::Image,
     pub door:   graphics::Image,
-    pub key:   graphics::Image
+    pub key:   graphics::Image,
+    pub player_walk: WalkCycle,
+    pub bot_walk: WalkCycle
 }
 
 impl Assets {
@@ -44,8 +96,21 @@ impl Assets {
         let door = graphics::Image::new(ctx, DOOR_IMG)?;
         let key = graphics::Image::new(ctx, KEY_IMG)?;
 
+        let player_walk = WalkCycle {
+            up: load_walk_frames(ctx, &player_up, PLAYER_UP_IMG)?,
+            down: load_walk_frames(ctx, &player_down, PLAYER_DOWN_IMG)?,
+            left: load_walk_frames(ctx, &player_left, PLAYER_LEFT_IMG)?,
+            right: load_walk_frames(ctx, &player_right, PLAYER_RIGHT_IMG)?
+        };
+        let bot_walk = WalkCycle {
+            up: load_walk_frames(ctx, &bot_up, BOT_UP_IMG)?,
+            down: load_walk_frames(ctx, &bot_down, BOT_DOWN_IMG)?,
+            left: load_walk_frames(ctx, &bot_left, BOT_LEFT_IMG)?,
+            right: load_walk_frames(ctx, &bot_right, BOT_RIGHT_IMG)?
+        };
+
         Ok(Assets {
-            player_up, player_down, player_left, player_right, bot_up, bot_down, bot_left, bot_right, floor, wall, door, key
+            player_up, player_down, player_left, player_right, bot_up, bot_down, bot_left, bot_right, floor, wall, door, key, player_walk, bot_walk
         })
     }
 }
\ No newline at end of file
@@ -1,186 +1,366 @@
-use ggez::{
-    graphics::{self, DrawParam},
-    Context,
-    GameResult,
-    mint::{Point2}
-};
-use rand::{self, Rng};
-use crate::assets::Assets;
-
-const UP: char = 'W';
-const DOWN: char = 'S';
-const LEFT: char = 'A';
-const RIGHT: char = 'D';
-
-#[derive(Clone, Copy)]
+use std::fmt;
+use std::rc::Rc;
+use std::str::FromStr;
+use rand::rngs::StdRng;
+use crate::grid::{Grid, Tile};
+use crate::direction::Direction;
+use crate::pathfinding;
+use crate::brain::{self, BotBrain, WorldView, BotView};
+use crate::difficulty::{Acceleration, Difficulty, Preset};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Behaviour
+{
+    // picks random turns at crossroads and only reacts to a player straight ahead
+    RandomWalker,
+    // walks around at random, chases a player it sees and searches where it lost them
+    Wanderer,
+    // follows the shortest path to the player, recomputed every `repath_interval` seconds
+    Hunter { repath_interval: f32, turn_cost: u32 },
+    // like the hunter, but heads for the tile `lookahead` steps in front of the player to cut them off
+    Ambusher { lookahead: usize, repath_interval: f32, turn_cost: u32 },
+    // keeps within `radius` tiles of its post and only chases a player who comes that close
+    Guard { radius: usize },
+    // walks its patrol route in a loop, breaks off to chase a player it sees and returns afterwards
+    Patroller,
+    // steered by the brain of its `BotConfig`, gets a patrol route and a post to use as it likes
+    Custom
+}
+
+impl Behaviour
+{
+    pub fn hunter() -> Self
+    {
+        Behaviour::Hunter{repath_interval: 0.5, turn_cost: pathfinding::STEP_COST / 2}
+    }
+
+    pub fn ambusher() -> Self
+    {
+        Behaviour::Ambusher{lookahead: 4, repath_interval: 0.5, turn_cost: pathfinding::STEP_COST / 2}
+    }
+
+    pub fn guard() -> Self
+    {
+        Behaviour::Guard{radius: 4}
+    }
+
+    pub fn brain(&self) -> Box<dyn BotBrain>
+    {
+        match *self
+        {
+            Behaviour::RandomWalker => Box::new(brain::RandomWalker),
+            Behaviour::Wanderer => Box::new(brain::Wanderer::default()),
+            Behaviour::Hunter{repath_interval, turn_cost} => Box::new(brain::Hunter::new(repath_interval, turn_cost)),
+            Behaviour::Ambusher{lookahead, repath_interval, turn_cost} => Box::new(brain::Ambusher::new(lookahead, repath_interval, turn_cost)),
+            Behaviour::Guard{radius} => Box::new(brain::Guard::new(radius)),
+            Behaviour::Patroller => Box::new(brain::Patroller::new()),
+            // only without a config to build the real one from
+            Behaviour::Custom => Box::new(brain::RandomWalker)
+        }
+    }
+}
+
+impl FromStr for Behaviour
+{
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err>
+    {
+        match name.to_lowercase().as_str()
+        {
+            "random" => Ok(Behaviour::RandomWalker),
+            "wanderer" => Ok(Behaviour::Wanderer),
+            "hunter" | "chaser" => Ok(Behaviour::hunter()),
+            "ambusher" => Ok(Behaviour::ambusher()),
+            "guard" => Ok(Behaviour::guard()),
+            "patroller" => Ok(Behaviour::Patroller),
+            _ => Err(format!("unknown bot behaviour `{}`", name))
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Vision
+{
+    // how many tiles away the bot notices anything, measured as a straight line
+    pub range: usize,
+    // full angle in degrees of the cone in front of the bot, None sees all around
+    pub field_of_view: Option<f32>
+}
+
+impl Default for Vision
+{
+    fn default() -> Self
+    {
+        Vision{range: 10, field_of_view: None}
+    }
+}
+
+// builds a new brain for every bot of a profile, so AIs written outside the crate can be picked per level
+#[derive(Clone)]
+pub struct BrainFactory(Rc<dyn Fn() -> Box<dyn BotBrain>>);
+
+impl BrainFactory
+{
+    pub fn new<F: Fn() -> Box<dyn BotBrain> + 'static>(build: F) -> Self
+    {
+        BrainFactory(Rc::new(build))
+    }
+
+    pub fn build(&self) -> Box<dyn BotBrain>
+    {
+        (self.0)()
+    }
+}
+
+// two factories are equal when they are the same one
+impl PartialEq for BrainFactory
+{
+    fn eq(&self, other: &Self) -> bool
+    {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl fmt::Debug for BrainFactory
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        write!(f, "BrainFactory")
+    }
+}
+
+// one bot of a level, the simulation spawns one bot per entry
+#[derive(Clone, PartialEq, Debug)]
+pub struct BotConfig
+{
+    pub behaviour: Behaviour,
+    // seconds between two steps at the start of the level, lower is faster. None takes the difficulty's
+    pub time_for_step: Option<f32>,
+    // the brain of a custom bot, None uses the one of `behaviour`
+    pub brain: Option<BrainFactory>
+}
+
+impl BotConfig
+{
+    pub fn new(behaviour: Behaviour) -> Self
+    {
+        BotConfig{behaviour: behaviour, time_for_step: None, brain: None}
+    }
+
+    pub fn custom(brain: BrainFactory) -> Self
+    {
+        BotConfig{behaviour: Behaviour::Custom, time_for_step: None, brain: Some(brain)}
+    }
+}
+
+impl FromStr for BotConfig
+{
+    type Err = String;
+
+    // `<behaviour>` or `<behaviour>:<seconds per step>`, e.g. `guard` or `hunter:0.2`
+    fn from_str(value: &str) -> Result<Self, Self::Err>
+    {
+        let (name, time_for_step) = match value.split_once(':')
+        {
+            Some((name, time_for_step)) => (name, Some(time_for_step)),
+            None => (value, None)
+        };
+        let mut config = BotConfig::new(name.parse::<Behaviour>()?);
+        if let Some(time_for_step) = time_for_step
+        {
+            config.time_for_step = match time_for_step.parse::<f32>()
+            {
+                Ok(time_for_step) if time_for_step > 0.0 => Some(time_for_step),
+                _ => return Err(format!("invalid step time `{}`", time_for_step))
+            };
+        }
+        Ok(config)
+    }
+}
+
+// what the bot remembers about the player
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BotState
+{
+    // no idea where the player is, walks around at random
+    Patrol,
+    // heading for the place the player was seen last
+    Chase,
+    // lost the player there and looks around the nearby corridors for a while
+    Search
+}
+
 pub struct Bot
 {
     pub x: usize,
     pub y: usize,
+    // the cell the bot stepped from, only used to draw the move
+    pub previous: (usize, usize),
     pub time_until_next_step: f32,
     pub time_for_step: f32,
-    pub direction: char,
-    pub is_on_exit: bool,
-    pub is_on_key: bool
+    // how `speed_up` shortens the step time, it never goes below `min_time_for_step`
+    pub acceleration: Acceleration,
+    pub min_time_for_step: f32,
+    pub direction: Direction,
+    pub behaviour: Behaviour,
+    // decides every step, built from `behaviour` unless it is replaced with a custom one
+    pub brain: Box<dyn BotBrain>,
+    pub vision: Vision,
+    pub state: BotState,
+    pub last_seen: Option<(usize, usize)>,
+    // how long the bot keeps searching around `last_seen` before it goes back to patrolling
+    pub search_duration: f32,
+    pub time_until_search_ends: f32,
+    // the tile a guard stays close to
+    pub post: Option<(usize, usize)>,
+    // waypoints a patroller visits in a loop
+    pub route: Vec<(usize, usize)>
 }
 
 impl Bot
 {
     pub fn new(x: usize, y: usize) -> Self
     {
-        let directions = ['W', 'D', 'A', 'S'];
-        let mut rng = rand::thread_rng();
-        let random_number = rng.gen_range(0..4);
-        Bot{x: x, y: y, time_until_next_step: 0.1, time_for_step: 0.1, direction: directions[random_number], is_on_exit: false, is_on_key: false}
+        Bot::with_behaviour(x, y, Behaviour::Wanderer)
     }
 
-    pub fn update_position(&mut self, x: usize, y: usize, is_on_exit: bool, is_on_key: bool)
+    pub fn with_behaviour(x: usize, y: usize, behaviour: Behaviour) -> Self
     {
-        self.x = x;
-        self.y = y;
-        self.is_on_exit = is_on_exit;
-        self.is_on_key = is_on_key;
+        let preset = Difficulty::default().preset();
+        Bot{x: x, y: y, previous: (x, y), time_until_next_step: preset.time_for_step, time_for_step: preset.time_for_step, acceleration: preset.acceleration, min_time_for_step: preset.min_time_for_step, direction: Direction::Down, behaviour: behaviour, brain: behaviour.brain(), vision: Vision{range: preset.vision_range, field_of_view: None}, state: BotState::Patrol, last_seen: None, search_duration: 5.0, time_until_search_ends: 0.0, post: None, route: Vec::new()}
     }
 
-    pub fn update_direction(&mut self, is_on_cross_road: bool)
+    pub fn from_config(x: usize, y: usize, config: &BotConfig, preset: &Preset) -> Self
     {
-        let mut directions = vec!['W', 'D', 'A', 'S'];
-        if is_on_cross_road
+        let mut bot = Bot::with_behaviour(x, y, config.behaviour);
+        if let Some(brain) = &config.brain
         {
-            let opposite_direction = get_oposite_direction(self.direction);
-            let index = directions.iter().position(|x| *x == opposite_direction).unwrap();
-            directions.remove(index);
+            bot.brain = brain.build();
         }
-        let mut rng = rand::thread_rng();
-        let random_number = rng.gen_range(0..directions.len());
-        self.direction = directions[random_number];
+        bot.time_for_step = config.time_for_step.unwrap_or(preset.time_for_step);
+        bot.time_until_next_step = bot.time_for_step;
+        bot.acceleration = preset.acceleration;
+        bot.min_time_for_step = preset.min_time_for_step;
+        bot.vision.range = preset.vision_range;
+        bot
+    }
+
+    pub fn update_position(&mut self, x: usize, y: usize)
+    {
+        self.x = x;
+        self.y = y;
     }
 
+    // keeps the time left over like the player's timer does, so equal step times mean equal speeds
     pub fn restart_timer(&mut self)
     {
-        self.time_until_next_step = self.time_for_step;
+        self.time_until_next_step += self.time_for_step;
     }
 
-    pub fn look_for_player(&mut self, maze: Vec<Vec<char>>)
+    pub fn can_see(&self, grid: &Grid<Tile>, target: (usize, usize)) -> bool
     {
-        let mut tmp_x = self.x;
-        let mut tmp_y = self.y;
+        let dx = target.0 as f32 - self.x as f32;
+        let dy = target.1 as f32 - self.y as f32;
+        if dx == 0.0 && dy == 0.0
+        {
+            return true;
+        }
 
-        while maze[tmp_y][tmp_x] != 'W'
+        let range = self.vision.range as f32;
+        if dx*dx + dy*dy > range*range
         {
-            if maze[tmp_y][tmp_x] == 'P'
-            {
-                self.direction = 'W';
-                return;
-            }
-            tmp_y -= 1;
+            return false;
         }
-        while maze[tmp_y][tmp_x] != 'W'
+
+        if let Some(field_of_view) = self.vision.field_of_view
         {
-            if maze[tmp_y][tmp_x] == 'P'
+            let (facing_x, facing_y) = self.direction.delta();
+            let cos_angle = (dx*facing_x as f32 + dy*facing_y as f32) / (dx*dx + dy*dy).sqrt();
+            if cos_angle < (field_of_view.to_radians() / 2.0).cos()
             {
-                self.direction = 'A';
-                return;
+                return false;
             }
-            tmp_x -= 1;
         }
 
-        let mut tmp_x = self.x;
-        let mut tmp_y = self.y;
+        grid.has_line_of_sight((self.x, self.y), target)
+    }
 
-        while maze[tmp_y][tmp_x] != 'W'
+    // updates what the bot remembers about the player, called on every tick.
+    // Returns whether the bot knows where the player is right now.
+    pub fn perceive(&mut self, grid: &Grid<Tile>, player: (usize, usize), dt: f32) -> bool
+    {
+        if self.can_see(grid, player) && self.is_near_post(player)
         {
-            if maze[tmp_y][tmp_x] == 'P'
-            {
-                self.direction = 'S';
-                return;
-            }
-            tmp_y += 1;
+            self.last_seen = Some(player);
+            self.state = BotState::Chase;
+            return true;
         }
-        while maze[tmp_y][tmp_x] != 'W'
+
+        match self.state
         {
-            if maze[tmp_y][tmp_x] == 'P'
+            BotState::Chase =>
             {
-                self.direction = 'D';
-                return;
+                if self.last_seen == Some((self.x, self.y))
+                {
+                    self.start_search();
+                }
             }
-            tmp_x += 1;
+            BotState::Search =>
+            {
+                self.time_until_search_ends -= dt;
+                if self.time_until_search_ends <= 0.0
+                {
+                    self.state = BotState::Patrol;
+                    self.last_seen = None;
+                }
+            }
+            BotState::Patrol => ()
         }
+        false
     }
-    
-    pub fn check_for_cross_road(&self, map: Vec<Vec<char>>) -> bool
+
+    fn start_search(&mut self)
     {
-        let mut count = 0;
-        if map[self.y][self.x+1] == '.'
-        {
-            count+=1;
-        }
-        if map[self.y][self.x-1] == '.'
-        {
-            count+=1;
-        }
-        if map[self.y+1][self.x] == '.'
-        {
-            count+=1;
-        }
-        if map[self.y-1][self.x] == '.'
-        {
-            count+=1;
-        }
+        self.state = BotState::Search;
+        self.time_until_search_ends = self.search_duration;
+    }
 
-        if count > 2
+    // a guard ignores a player who is too far from its post, every other bot notices them anywhere
+    fn is_near_post(&self, target: (usize, usize)) -> bool
+    {
+        match (self.post, self.behaviour)
         {
-            return true;
+            (Some(post), Behaviour::Guard{radius}) => target.0.abs_diff(post.0) + target.1.abs_diff(post.1) <= radius,
+            _ => true
         }
-        false
     }
 
-    pub fn speed_up(&mut self)
+    fn bot_view(&self) -> BotView
     {
-        self.time_for_step -= 0.001;
+        BotView{position: (self.x, self.y), direction: self.direction, state: self.state, last_seen: self.last_seen, post: self.post}
     }
-    
-    pub fn draw(&self, ctx: &mut Context, assets: &Assets, x_sq: i32, y_sq: i32) -> GameResult
+
+    // the bot as its brain gets to see the world, `player` is the player's position and facing if the bot knows them
+    pub fn view<'a>(&'a self, grid: &'a Grid<Tile>, player: Option<((usize, usize), Direction)>, elapsed: f32) -> WorldView<'a>
     {
-        let draw_param = DrawParam::new().dest(Point2{x:x_sq as f32, y:y_sq as f32});
-        graphics::draw(ctx, &assets.floor, draw_param)?;
-        if self.is_on_exit
-        {
-            graphics::draw(ctx, &assets.door, draw_param)?;
-        }
+        WorldView::new(grid, self.bot_view(), &self.route, player, elapsed)
+    }
 
-        match self.direction
-        {
-            UP =>
-            {
-                graphics::draw(ctx, &assets.bot_up, draw_param)?;
-            }
-            DOWN =>
-            {
-                graphics::draw(ctx, &assets.bot_down, draw_param)?;
-            }
-            LEFT =>
-            {
-                graphics::draw(ctx, &assets.bot_left, draw_param)?;
-            }
-            RIGHT =>
-            {
-                graphics::draw(ctx, &assets.bot_right, draw_param)?;
-            }
-            _ => ()
-        }
-        Ok(())
+    // asks the brain for the next step and faces that way, the caller decides whether the bot can actually move there
+    pub fn think(&mut self, grid: &Grid<Tile>, player: Option<((usize, usize), Direction)>, elapsed: f32, rng: &mut StdRng) -> Option<Direction>
+    {
+        // built from the fields rather than `view` so the brain can be borrowed mutably next to the route
+        let view = WorldView::new(grid, self.bot_view(), &self.route, player, elapsed);
+        let direction = self.brain.next_move(&view, rng)?;
+        self.direction = direction;
+        Some(direction)
     }
-}
 
-fn get_oposite_direction(direction: char) -> char
+    // a bot that started out faster than the minimum keeps its speed
+    pub fn speed_up(&mut self)
     {
-        match direction
-        {
-            UP => DOWN,
-            DOWN => UP,
-            LEFT => RIGHT,
-            RIGHT => LEFT,
-            _ => 'q'
-        }
-    }
\ No newline at end of file
+        let floor = self.min_time_for_step.min(self.time_for_step);
+        self.time_for_step = self.acceleration.apply(self.time_for_step).max(floor);
+    }
+}
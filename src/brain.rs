@@ -0,0 +1,431 @@
+use std::collections::VecDeque;
+use rand::Rng;
+use rand::rngs::StdRng;
+use crate::bot::BotState;
+use crate::grid::{Grid, Tile};
+use crate::direction::Direction;
+use crate::pathfinding;
+
+// what a brain knows about the bot it steers
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BotView
+{
+    pub position: (usize, usize),
+    pub direction: Direction,
+    pub state: BotState,
+    pub last_seen: Option<(usize, usize)>,
+    pub post: Option<(usize, usize)>
+}
+
+// A read-only snapshot of the world from the point of view of one bot. The player is only
+// filled in while the bot can see them.
+#[derive(Clone, Copy)]
+pub struct WorldView<'a>
+{
+    pub grid: &'a Grid<Tile>,
+    pub bot: BotView,
+    pub player: Option<(usize, usize)>,
+    pub player_direction: Option<Direction>,
+    // the waypoints of the bot's patrol route in the order they are visited, empty for most bots
+    pub route: &'a [(usize, usize)],
+    // seconds since the brain was last asked for a move
+    pub elapsed: f32
+}
+
+impl<'a> WorldView<'a>
+{
+    pub fn new(grid: &'a Grid<Tile>, bot: BotView, route: &'a [(usize, usize)], player: Option<((usize, usize), Direction)>, elapsed: f32) -> Self
+    {
+        WorldView
+        {
+            grid: grid,
+            bot: bot,
+            player: player.map(|(position, _)| position),
+            player_direction: player.map(|(_, direction)| direction),
+            route: route,
+            elapsed: elapsed
+        }
+    }
+}
+
+// Decides where a bot goes. The simulation asks once per step of the bot and moves it one tile
+// in the returned direction, a wall or another bot in the way keeps it where it is.
+// All randomness has to come from `rng` so a seed replays the same game.
+pub trait BotBrain
+{
+    // the direction of the next step, None stays on the current tile
+    fn next_move(&mut self, view: &WorldView, rng: &mut StdRng) -> Option<Direction>;
+}
+
+// the direction towards a player in view who stands in the same row or column
+pub fn look_for_player(view: &WorldView) -> Option<Direction>
+{
+    let (x, y) = view.bot.position;
+    let player = view.player?;
+    if player.0 != x && player.1 != y
+    {
+        return None;
+    }
+
+    if player.1 < y
+    {
+        Some(Direction::Up)
+    }
+    else if player.1 > y
+    {
+        Some(Direction::Down)
+    }
+    else if player.0 < x
+    {
+        Some(Direction::Left)
+    }
+    else if player.0 > x
+    {
+        Some(Direction::Right)
+    }
+    else
+    {
+        None
+    }
+}
+
+fn open_directions(view: &WorldView) -> Vec<Direction>
+{
+    let (x, y) = view.bot.position;
+    Direction::ALL.into_iter().filter(|direction| direction.step(x, y).is_some_and(|(x, y)| view.grid.is_walkable(x, y))).collect()
+}
+
+fn choose(options: &[Direction], rng: &mut StdRng) -> Option<Direction>
+{
+    if options.is_empty()
+    {
+        return None;
+    }
+    Some(options[rng.gen_range(0..options.len())])
+}
+
+// The next tile of `path` towards `target`, the path is planned again when `replan` is set
+// or the bot is no longer standing at its start.
+fn follow_path(path: &mut VecDeque<(usize, usize)>, view: &WorldView, target: (usize, usize), turn_cost: u32, replan: bool) -> Option<Direction>
+{
+    let position = view.bot.position;
+    let is_on_path = path.front().is_some_and(|next| Direction::between(position, *next).is_some());
+    if replan || !is_on_path
+    {
+        let new_path = pathfinding::find_path(view.grid, position, Some(view.bot.direction), target, turn_cost);
+        *path = new_path.unwrap_or_default().into();
+    }
+    let next = path.pop_front()?;
+    Direction::between(position, next)
+}
+
+// a random walk that avoids turning back and stays within `radius` of `center`,
+// it only turns around when every other way leads out of that area or into a wall
+fn wander_near(view: &WorldView, center: (usize, usize), radius: usize, rng: &mut StdRng) -> Option<Direction>
+{
+    let (x, y) = view.bot.position;
+    let behind = view.bot.direction.opposite();
+    let open: Vec<(Direction, usize)> = open_directions(view).into_iter().filter_map(|direction|
+    {
+        let (x, y) = direction.step(x, y)?;
+        Some((direction, x.abs_diff(center.0) + y.abs_diff(center.1)))
+    }).collect();
+
+    let preferences = [
+        open.iter().filter(|(direction, distance)| *direction != behind && *distance <= radius).map(|(direction, _)| *direction).collect::<Vec<Direction>>(),
+        open.iter().filter(|(_, distance)| *distance <= radius).map(|(direction, _)| *direction).collect(),
+        open.iter().map(|(direction, _)| *direction).collect()
+    ];
+    let options = preferences.iter().find(|options| !options.is_empty())?;
+    choose(options, rng)
+}
+
+// The original bot: keeps walking straight, takes a random turn at crossroads and walls
+// and heads for a player it sees down a corridor.
+pub struct RandomWalker;
+
+impl BotBrain for RandomWalker
+{
+    fn next_move(&mut self, view: &WorldView, rng: &mut StdRng) -> Option<Direction>
+    {
+        if let Some(direction) = look_for_player(view)
+        {
+            return Some(direction);
+        }
+
+        let open = open_directions(view);
+        let is_on_cross_road = open.len() > 2;
+        if !is_on_cross_road && open.contains(&view.bot.direction)
+        {
+            return Some(view.bot.direction);
+        }
+        // turning back is the last resort, it's the only way out of a dead end
+        let behind = view.bot.direction.opposite();
+        let forward: Vec<Direction> = open.iter().copied().filter(|direction| *direction != behind).collect();
+        if forward.is_empty()
+        {
+            choose(&open, rng)
+        }
+        else
+        {
+            choose(&forward, rng)
+        }
+    }
+}
+
+// Patrols like the random walker, heads for the place it last saw the player and searches
+// the corridors around it before patrolling again.
+pub struct Wanderer
+{
+    pub search_radius: usize,
+    pub path: VecDeque<(usize, usize)>
+}
+
+impl Wanderer
+{
+    pub fn new(search_radius: usize) -> Self
+    {
+        Wanderer{search_radius: search_radius, path: VecDeque::new()}
+    }
+}
+
+impl Default for Wanderer
+{
+    fn default() -> Self
+    {
+        Wanderer::new(6)
+    }
+}
+
+impl BotBrain for Wanderer
+{
+    fn next_move(&mut self, view: &WorldView, rng: &mut StdRng) -> Option<Direction>
+    {
+        match (view.bot.state, view.bot.last_seen)
+        {
+            (BotState::Chase, Some(target)) if target != view.bot.position =>
+            {
+                // the old path leads to where the player used to be
+                let replan = self.path.back() != Some(&target);
+                follow_path(&mut self.path, view, target, 0, replan).or_else(|| wander_near(view, target, self.search_radius, rng))
+            }
+            (BotState::Chase, _) | (BotState::Search, _) =>
+            {
+                let center = view.bot.last_seen.unwrap_or(view.bot.position);
+                wander_near(view, center, self.search_radius, rng)
+            }
+            (BotState::Patrol, _) => RandomWalker.next_move(view, rng)
+        }
+    }
+}
+
+// Follows the shortest path to the player while it sees them, or to where it last saw them,
+// planned again every `repath_interval` seconds. Patrols like the random walker otherwise.
+pub struct Hunter
+{
+    pub repath_interval: f32,
+    pub turn_cost: u32,
+    pub path: VecDeque<(usize, usize)>,
+    time_until_repath: f32
+}
+
+impl Hunter
+{
+    pub fn new(repath_interval: f32, turn_cost: u32) -> Self
+    {
+        Hunter{repath_interval: repath_interval, turn_cost: turn_cost, path: VecDeque::new(), time_until_repath: 0.0}
+    }
+
+    fn move_towards(&mut self, view: &WorldView, target: (usize, usize)) -> Option<Direction>
+    {
+        self.time_until_repath -= view.elapsed;
+        let replan = self.time_until_repath <= 0.0;
+        if replan
+        {
+            self.time_until_repath = self.repath_interval;
+        }
+        follow_path(&mut self.path, view, target, self.turn_cost, replan)
+    }
+}
+
+impl BotBrain for Hunter
+{
+    fn next_move(&mut self, view: &WorldView, rng: &mut StdRng) -> Option<Direction>
+    {
+        match view.player.or(view.bot.last_seen)
+        {
+            Some(target) if target != view.bot.position => self.move_towards(view, target),
+            _ => RandomWalker.next_move(view, rng)
+        }
+    }
+}
+
+// like the hunter, but heads for the tile `lookahead` steps in front of a player it sees to cut them off
+pub struct Ambusher
+{
+    pub lookahead: usize,
+    pub hunter: Hunter
+}
+
+impl Ambusher
+{
+    pub fn new(lookahead: usize, repath_interval: f32, turn_cost: u32) -> Self
+    {
+        Ambusher{lookahead: lookahead, hunter: Hunter::new(repath_interval, turn_cost)}
+    }
+
+    // up to `lookahead` tiles in front of the player, stopping at walls.
+    // Once the bot is that close anyway it goes for the player directly.
+    pub fn target(&self, view: &WorldView) -> Option<(usize, usize)>
+    {
+        let player = view.player?;
+        let (x, y) = view.bot.position;
+        if x.abs_diff(player.0) + y.abs_diff(player.1) <= self.lookahead
+        {
+            return Some(player);
+        }
+
+        let facing = match view.player_direction
+        {
+            Some(facing) => facing,
+            None => return Some(player)
+        };
+        let mut target = player;
+        for _ in 0..self.lookahead
+        {
+            match facing.step(target.0, target.1)
+            {
+                Some(next) if view.grid.is_walkable(next.0, next.1) => target = next,
+                _ => break
+            }
+        }
+        Some(target)
+    }
+}
+
+impl BotBrain for Ambusher
+{
+    fn next_move(&mut self, view: &WorldView, rng: &mut StdRng) -> Option<Direction>
+    {
+        match self.target(view).or(view.bot.last_seen)
+        {
+            Some(target) if target != view.bot.position => self.hunter.move_towards(view, target),
+            _ => RandomWalker.next_move(view, rng)
+        }
+    }
+}
+
+// Keeps within `radius` tiles of the bot's post, a guard that drifted away walks back to it.
+// Chasing and searching work like the wanderer's.
+pub struct Guard
+{
+    pub radius: usize,
+    pub wanderer: Wanderer,
+    path: VecDeque<(usize, usize)>
+}
+
+impl Guard
+{
+    pub fn new(radius: usize) -> Self
+    {
+        Guard{radius: radius, wanderer: Wanderer::default(), path: VecDeque::new()}
+    }
+}
+
+impl BotBrain for Guard
+{
+    fn next_move(&mut self, view: &WorldView, rng: &mut StdRng) -> Option<Direction>
+    {
+        let post = match (view.bot.state, view.bot.post)
+        {
+            (BotState::Patrol, Some(post)) => post,
+            _ => return self.wanderer.next_move(view, rng)
+        };
+        let (x, y) = view.bot.position;
+        if x.abs_diff(post.0) + y.abs_diff(post.1) <= self.radius
+        {
+            self.path.clear();
+            return wander_near(view, post, self.radius, rng);
+        }
+        // the post moves to the exit once the key is taken
+        let replan = self.path.back() != Some(&post);
+        follow_path(&mut self.path, view, post, 0, replan)
+    }
+}
+
+// Walks its patrol route from waypoint to waypoint and starts over at the end. A patroller that
+// sees the player chases and searches like the wanderer, then walks back to the closest waypoint.
+pub struct Patroller
+{
+    pub wanderer: Wanderer,
+    pub next_waypoint: usize,
+    path: VecDeque<(usize, usize)>,
+    was_patrolling: bool
+}
+
+impl Patroller
+{
+    pub fn new() -> Self
+    {
+        Patroller{wanderer: Wanderer::default(), next_waypoint: 0, path: VecDeque::new(), was_patrolling: true}
+    }
+}
+
+impl Default for Patroller
+{
+    fn default() -> Self
+    {
+        Patroller::new()
+    }
+}
+
+impl BotBrain for Patroller
+{
+    fn next_move(&mut self, view: &WorldView, rng: &mut StdRng) -> Option<Direction>
+    {
+        if view.bot.state != BotState::Patrol || view.route.is_empty()
+        {
+            self.was_patrolling = false;
+            return self.wanderer.next_move(view, rng);
+        }
+
+        let (x, y) = view.bot.position;
+        if !self.was_patrolling
+        {
+            // back from a chase, pick the route up where it is closest
+            self.was_patrolling = true;
+            self.next_waypoint = (0..view.route.len()).min_by_key(|index| view.route[*index].0.abs_diff(x) + view.route[*index].1.abs_diff(y)).unwrap_or(0);
+        }
+        self.next_waypoint %= view.route.len();
+        if view.route[self.next_waypoint] == (x, y)
+        {
+            self.next_waypoint = (self.next_waypoint + 1) % view.route.len();
+        }
+        let waypoint = view.route[self.next_waypoint];
+        let replan = self.path.back() != Some(&waypoint);
+        follow_path(&mut self.path, view, waypoint, 0, replan)
+    }
+}
+
+// A patrol loop for a bot starting at `start` through the `length` dead ends closest to it,
+// in the order they are reached. A maze without enough dead ends falls back to the farthest tiles.
+pub fn generate_route(grid: &Grid<Tile>, start: (usize, usize), length: usize) -> Vec<(usize, usize)>
+{
+    let distances = pathfinding::distances(grid, start);
+    let mut dead_ends: Vec<((usize, usize), usize)> = grid.dead_ends().filter(|position| *position != start).filter_map(|position| Some((position, distances[position]?))).collect();
+    dead_ends.sort_by_key(|(position, distance)| (*distance, position.1, position.0));
+    let mut route: Vec<(usize, usize)> = dead_ends.into_iter().take(length).map(|(position, _)| position).collect();
+
+    if route.len() < 2
+    {
+        let mut farthest: Vec<((usize, usize), usize)> = distances.iter().filter_map(|(position, distance)| Some((position, (*distance)?))).filter(|(position, _)| !route.contains(position)).collect();
+        farthest.sort_by_key(|(position, distance)| (usize::MAX - *distance, position.1, position.0));
+        route.extend(farthest.into_iter().take(length.max(2) - route.len()).map(|(position, _)| position));
+    }
+    // coming back to the start closes the loop
+    if !route.is_empty()
+    {
+        route.insert(0, start);
+    }
+    route
+}
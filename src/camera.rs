@@ -0,0 +1,100 @@
+use std::ops::Range;
+
+// A view onto the maze, which can be far bigger than the window. Everything is in world pixels,
+// where a tile is `cell_size` wide, the zoom only decides how much of the world fits the window.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Camera
+{
+    // the world point in the middle of the window
+    pub x: f32,
+    pub y: f32,
+    pub zoom: f32,
+    pub min_zoom: f32,
+    pub max_zoom: f32,
+    // size of the window in screen pixels
+    pub viewport: (f32, f32),
+    // how quickly the camera catches up with its target, higher is snappier
+    pub follow_speed: f32
+}
+
+impl Camera
+{
+    pub fn new(viewport_width: f32, viewport_height: f32) -> Self
+    {
+        Camera{x: viewport_width / 2.0, y: viewport_height / 2.0, zoom: 1.0, min_zoom: 0.25, max_zoom: 3.0, viewport: (viewport_width, viewport_height), follow_speed: 6.0}
+    }
+
+    // size of the visible part of the world
+    pub fn view_size(&self) -> (f32, f32)
+    {
+        (self.viewport.0 / self.zoom, self.viewport.1 / self.zoom)
+    }
+
+    // left, top, width and height of the visible part of the world
+    pub fn view_rect(&self) -> (f32, f32, f32, f32)
+    {
+        let (width, height) = self.view_size();
+        (self.x - width / 2.0, self.y - height / 2.0, width, height)
+    }
+
+    // moves part of the way towards `target`, so the camera eases in behind the player
+    pub fn follow(&mut self, target: (f32, f32), world_size: (f32, f32), dt: f32)
+    {
+        let share = 1.0 - (-self.follow_speed * dt).exp();
+        self.x += (target.0 - self.x) * share;
+        self.y += (target.1 - self.y) * share;
+        self.clamp(world_size);
+    }
+
+    pub fn jump_to(&mut self, target: (f32, f32), world_size: (f32, f32))
+    {
+        self.x = target.0;
+        self.y = target.1;
+        self.clamp(world_size);
+    }
+
+    pub fn set_zoom(&mut self, zoom: f32, world_size: (f32, f32))
+    {
+        self.zoom = zoom.clamp(self.min_zoom, self.max_zoom);
+        self.clamp(world_size);
+    }
+
+    pub fn zoom_by(&mut self, factor: f32, world_size: (f32, f32))
+    {
+        self.set_zoom(self.zoom * factor, world_size);
+    }
+
+    // keeps the view inside the world, a world smaller than the view is centred
+    fn clamp(&mut self, world_size: (f32, f32))
+    {
+        let (width, height) = self.view_size();
+        self.x = clamp_axis(self.x, width, world_size.0);
+        self.y = clamp_axis(self.y, height, world_size.1);
+    }
+
+    // the columns and rows of the tiles that are at least partly in view
+    pub fn visible_cells(&self, cell_size: f32, columns: usize, rows: usize) -> (Range<usize>, Range<usize>)
+    {
+        let (left, top, width, height) = self.view_rect();
+        (visible_range(left, width, cell_size, columns), visible_range(top, height, cell_size, rows))
+    }
+}
+
+fn clamp_axis(center: f32, view: f32, world: f32) -> f32
+{
+    if world <= view
+    {
+        world / 2.0
+    }
+    else
+    {
+        center.clamp(view / 2.0, world - view / 2.0)
+    }
+}
+
+fn visible_range(start: f32, length: f32, cell_size: f32, count: usize) -> Range<usize>
+{
+    let first = (start / cell_size).floor().max(0.0) as usize;
+    let last = (((start + length) / cell_size).ceil().max(0.0) as usize).min(count);
+    first.min(last)..last
+}
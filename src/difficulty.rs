@@ -0,0 +1,98 @@
+use std::str::FromStr;
+use crate::bot::Behaviour;
+
+// how the bots get faster over a game, applied once every second
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Acceleration
+{
+    // takes this many seconds off the step time
+    Linear(f32),
+    // multiplies the step time by this factor
+    Exponential(f32)
+}
+
+impl Acceleration
+{
+    pub fn apply(&self, time_for_step: f32) -> f32
+    {
+        match *self
+        {
+            Acceleration::Linear(seconds) => time_for_step - seconds,
+            Acceleration::Exponential(factor) => time_for_step * factor
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Preset
+{
+    // seconds between two bot steps at the start of the game
+    pub time_for_step: f32,
+    pub acceleration: Acceleration,
+    // the bots never get faster than one step per this many seconds
+    pub min_time_for_step: f32,
+    pub vision_range: usize,
+    // a level with more bot profiles than this still spawns every one of them
+    pub bot_count: usize,
+    // how the bots behave when the level doesn't set its own profiles
+    pub behaviour: Behaviour,
+    // every point scored is worth this many times as much
+    pub score_multiplier: f32
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum Difficulty
+{
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Nightmare
+}
+
+impl Difficulty
+{
+    pub const ALL: [Difficulty; 4] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard, Difficulty::Nightmare];
+
+    pub fn preset(&self) -> Preset
+    {
+        match self
+        {
+            Difficulty::Easy => Preset{time_for_step: 0.15, acceleration: Acceleration::Linear(0.0005), min_time_for_step: 0.1, vision_range: 6, bot_count: 1, behaviour: Behaviour::RandomWalker, score_multiplier: 1.0},
+            Difficulty::Normal => Preset{time_for_step: 0.1, acceleration: Acceleration::Linear(0.001), min_time_for_step: 0.06, vision_range: 10, bot_count: 1, behaviour: Behaviour::Wanderer, score_multiplier: 1.5},
+            Difficulty::Hard => Preset{time_for_step: 0.09, acceleration: Acceleration::Exponential(0.995), min_time_for_step: 0.05, vision_range: 12, bot_count: 2, behaviour: Behaviour::hunter(), score_multiplier: 2.0},
+            Difficulty::Nightmare => Preset{time_for_step: 0.08, acceleration: Acceleration::Exponential(0.99), min_time_for_step: 0.04, vision_range: 16, bot_count: 3, behaviour: Behaviour::hunter(), score_multiplier: 3.0}
+        }
+    }
+
+    // the next harder preset, nightmare stays nightmare
+    pub fn harder(&self) -> Difficulty
+    {
+        let index = Difficulty::ALL.iter().position(|difficulty| difficulty == self).unwrap_or(0);
+        Difficulty::ALL[(index + 1).min(Difficulty::ALL.len() - 1)]
+    }
+
+    // the next easier preset, easy stays easy
+    pub fn easier(&self) -> Difficulty
+    {
+        let index = Difficulty::ALL.iter().position(|difficulty| difficulty == self).unwrap_or(0);
+        Difficulty::ALL[index.saturating_sub(1)]
+    }
+}
+
+impl FromStr for Difficulty
+{
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err>
+    {
+        match name.to_lowercase().as_str()
+        {
+            "easy" => Ok(Difficulty::Easy),
+            "normal" => Ok(Difficulty::Normal),
+            "hard" => Ok(Difficulty::Hard),
+            "nightmare" => Ok(Difficulty::Nightmare),
+            _ => Err(format!("unknown difficulty `{}`", name))
+        }
+    }
+}
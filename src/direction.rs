@@ -0,0 +1,71 @@
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Direction
+{
+    Up,
+    Down,
+    Left,
+    Right
+}
+
+impl Direction
+{
+    pub const ALL: [Direction; 4] = [Direction::Up, Direction::Right, Direction::Left, Direction::Down];
+
+    pub fn opposite(&self) -> Direction
+    {
+        match self
+        {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left
+        }
+    }
+
+    pub fn turn_left(&self) -> Direction
+    {
+        match self
+        {
+            Direction::Up => Direction::Left,
+            Direction::Left => Direction::Down,
+            Direction::Down => Direction::Right,
+            Direction::Right => Direction::Up
+        }
+    }
+
+    pub fn turn_right(&self) -> Direction
+    {
+        match self
+        {
+            Direction::Up => Direction::Right,
+            Direction::Right => Direction::Down,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Up
+        }
+    }
+
+    // (dx, dy) of one step, y grows downwards like the rows of the map
+    pub fn delta(&self) -> (isize, isize)
+    {
+        match self
+        {
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0)
+        }
+    }
+
+    // the position one step away, None when it would go below zero
+    pub fn step(&self, x: usize, y: usize) -> Option<(usize, usize)>
+    {
+        let (dx, dy) = self.delta();
+        Some((x.checked_add_signed(dx)?, y.checked_add_signed(dy)?))
+    }
+
+    // the direction of a single step from one position to a neighboring one
+    pub fn between(from: (usize, usize), to: (usize, usize)) -> Option<Direction>
+    {
+        Direction::ALL.into_iter().find(|direction| direction.step(from.0, from.1) == Some(to))
+    }
+}
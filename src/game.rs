@@ -2,334 +2,495 @@ use ggez::{
     event::{self, EventHandler},
     conf::{Conf},
     graphics::{self, DrawParam},
-    Context, GameResult,
+    Context, GameResult, GameError,
     input,
     timer,
     mint::{Point2}
 };
 use std::{thread, time};
-use rand::{self, Rng};
 use crate::bot::Bot;
 use crate::player::Player;
-use crate::maze_generator::Graph;
 use crate::assets::Assets;
+use crate::animation::{self, Pose};
+use crate::camera::Camera;
+use crate::minimap::{self, Minimap};
+use crate::simulation::{Simulation, Input, Stats};
+use crate::grid::Tile;
+use crate::keys::Colour;
+use crate::direction::Direction;
+pub use crate::simulation::{State, Settings};
 
-const CELL_SIZE: i32 = 45;
-const WALL: char = 'W';
-const FLOOR: char = '.';
-const PLAYER: char = 'P';
-const BOT: char = 'E';
-const EXIT: char = 'V';
-const KEY: char = 'K';
-const UP: char = 'W';
-const DOWN: char = 'S';
-const LEFT: char = 'A';
-const RIGHT: char = 'D';
-
-#[derive(PartialEq, Debug)]
-pub enum State
+pub const CELL_SIZE: i32 = 45;
+// a bigger maze doesn't grow the window any further, the camera scrolls over it instead
+pub const MAX_WINDOW_SIZE: (f32, f32) = (1260.0, 945.0);
+// how much one press of + or - or one notch of the mouse wheel zooms
+const ZOOM_STEP: f32 = 1.25;
+
+pub fn window_size(settings: &Settings) -> (f32, f32)
 {
-    Start,
-    MainState,
-    Credits(String)
+    let width = (2*settings.width + 1) as i32 * CELL_SIZE;
+    let height = (2*settings.height + 1) as i32 * CELL_SIZE;
+    ((width as f32).min(MAX_WINDOW_SIZE.0), (height as f32).min(MAX_WINDOW_SIZE.1))
 }
 
 pub struct MazeGame
 {
-    pub player: Player,
-    pub ai: Bot,
+    pub simulation: Simulation,
     pub assets: Assets,
-    pub game_state: State,
-    pub map: Vec<Vec<char>>,
-    pub time_until_bot_speed_up: f32,
-    pub conf: Conf
+    pub conf: Conf,
+    // a movement key pressed since the last fixed update
+    pressed: Option<Direction>,
+    // movement keys currently held down, the latest one last
+    held: Vec<Direction>,
+    // toggled with F1, shows what the bot is thinking
+    show_debug: bool,
+    camera: Camera,
+    // toggled with M
+    pub minimap: Minimap
 }
 
 impl MazeGame {
-    pub fn new(ctx: &mut Context, conf: Conf) -> GameResult<Self>
+    pub fn new(ctx: &mut Context, conf: Conf, settings: Settings) -> GameResult<Self>
     {
         let assets = Assets::new(ctx)?;
+        let camera = Camera::new(conf.window_mode.width, conf.window_mode.height);
 
-        let mut graph = Graph::new(10, 10);
-        graph.generate_maze(0, 0);
-        let mut map =graph.draw_maze();
+        let mut maze = MazeGame
+        {
+            simulation: Simulation::new(settings).map_err(GameError::CustomError)?,
+            assets: assets,
+            conf: conf,
+            pressed: None,
+            held: Vec::new(),
+            show_debug: false,
+            camera: camera,
+            minimap: Minimap::default()
+        };
+        maze.camera.jump_to(maze.player_center(), maze.world_size());
+        Ok(maze)
+    }
 
-        let player = Player::new(1, 1);
+    fn world_size(&self) -> (f32, f32)
+    {
+        let map = &self.simulation.map;
+        ((map.width() as i32 * CELL_SIZE) as f32, (map.height() as i32 * CELL_SIZE) as f32)
+    }
 
-        let bot_location = generate_location(&map);
-        let ai = Bot::new(bot_location.x, bot_location.y);
+    // middle of the player's sprite as it is drawn, so the camera glides along with it
+    fn player_center(&self) -> (f32, f32)
+    {
+        let player = &self.simulation.player;
+        let progress = animation::step_progress(player.time_until_next_step, player.time_for_step);
+        let pose = animation::pose(player.previous, (player.x, player.y), progress, 1);
+        let cell_size = CELL_SIZE as f32;
+        ((pose.x + 0.5) * cell_size, (pose.y + 0.5) * cell_size)
+    }
 
-        map[player.y][player.x] = PLAYER;
+    fn zoom(&mut self, factor: f32)
+    {
+        let world_size = self.world_size();
+        self.camera.zoom_by(factor, world_size);
+    }
 
-        map[ai.y][ai.x] = BOT;
-        let exit_y = map.len() - 2;
-        let exit_x = map[0].len() - 2;
+    fn draw_world(&self, ctx: &mut Context) -> GameResult
+    {
+        let (left, top, width, height) = self.camera.view_rect();
+        graphics::set_screen_coordinates(ctx, graphics::Rect::new(left, top, width, height))?;
 
-        map[exit_y][exit_x] = EXIT;
+        // terrain first, the entities are drawn on top of whatever tile they stand on
+        let map = &self.simulation.map;
+        let fog = self.simulation.settings.fog.is_some();
+        let (columns, rows) = self.camera.visible_cells(CELL_SIZE as f32, map.width(), map.height());
+        let mut shadows = graphics::MeshBuilder::new();
+        let mut has_shadows = false;
+        for y in rows
+        {
+            for x in columns.clone()
+            {
+                // in the fog, unexplored tiles stay black and the ones out of sight are only remembered
+                if fog && !self.simulation.is_explored(x, y)
+                {
+                    continue;
+                }
+                draw_tile(ctx, &self.assets, map[(x, y)], x as i32 * CELL_SIZE, y as i32 * CELL_SIZE)?;
+                if !self.simulation.is_visible(x, y)
+                {
+                    let rectangle = graphics::Rect::new((x as i32 * CELL_SIZE) as f32, (y as i32 * CELL_SIZE) as f32, CELL_SIZE as f32, CELL_SIZE as f32);
+                    shadows.rectangle(graphics::DrawMode::fill(), rectangle, graphics::Color::new(0.0, 0.0, 0.0, 0.6))?;
+                    has_shadows = true;
+                }
+            }
+        }
+        if has_shadows
+        {
+            let shadows = shadows.build(ctx)?;
+            graphics::draw(ctx, &shadows, DrawParam::default())?;
+        }
 
-        let key_location = generate_location(&map);
-        map[key_location.y][key_location.x] = KEY;
-        
-        let maze = MazeGame
+        if let Some(hint) = &self.simulation.hint
         {
-            player: player,
-            ai: ai,
-            assets: assets,
-            game_state: State::Start,
-            map: map,
-            time_until_bot_speed_up: 1.0,
-            conf: conf
-        };
-        Ok(maze)
+            self.draw_hint(ctx, hint.time_left)?;
+        }
+
+        draw_player(ctx, &self.assets, &self.simulation.player)?;
+        for bot in self.simulation.visible_bots()
+        {
+            draw_bot(ctx, &self.assets, bot)?;
+        }
+        if self.show_debug
+        {
+            for bot in self.simulation.visible_bots()
+            {
+                self.draw_debug(ctx, bot)?;
+            }
+        }
+
+        // back to window pixels for everything that stays in place on screen
+        let window = graphics::Rect::new(0.0, 0.0, self.conf.window_mode.width, self.conf.window_mode.height);
+        graphics::set_screen_coordinates(ctx, window)
     }
 
-    pub fn update_player_position(&mut self, new_x: usize, new_y: usize, keycode: event::KeyCode)
+    // a dotted line along the way, fading out over the last second
+    fn draw_hint(&self, ctx: &mut Context, time_left: f32) -> GameResult
     {
-        let mut is_on_exit = false;
-        self.player.update_direction(keycode);
-        if self.map[new_y][new_x] == WALL 
+        let alpha = time_left.clamp(0.0, 1.0) * 0.8;
+        let mut builder = graphics::MeshBuilder::new();
+        for (x, y) in self.simulation.hint_tiles()
         {
-            return;
-        } 
-        else if self.map[new_y][new_x] == BOT 
+            let center = Point2{x: (x as i32 * CELL_SIZE + CELL_SIZE/2) as f32, y: (y as i32 * CELL_SIZE + CELL_SIZE/2) as f32};
+            builder.circle(graphics::DrawMode::fill(), center, 6.0, 0.5, graphics::Color::new(0.3, 0.9, 1.0, alpha))?;
+        }
+        let mesh = builder.build(ctx)?;
+        graphics::draw(ctx, &mesh, DrawParam::default())
+    }
+
+    fn draw_minimap(&self, ctx: &mut Context) -> GameResult
+    {
+        let map = &self.simulation.map;
+        let window = (self.conf.window_mode.width, self.conf.window_mode.height);
+        let (left, top, cell_size) = self.minimap.layout(window, map.width(), map.height());
+        let cell = |x: usize, y: usize| graphics::Rect::new(left + x as f32 * cell_size, top + y as f32 * cell_size, cell_size, cell_size);
+
+        let mut builder = graphics::MeshBuilder::new();
+        let background = graphics::Rect::new(left, top, map.width() as f32 * cell_size, map.height() as f32 * cell_size);
+        builder.rectangle(graphics::DrawMode::fill(), background, graphics::Color::new(0.0, 0.0, 0.0, 0.6))?;
+        for ((x, y), tile) in minimap::explored_tiles(&self.simulation)
         {
-            self.game_state = State::Credits(String::from("Game Over!"));
-            let ten_millis = time::Duration::from_secs_f32(0.5);
-            thread::sleep(ten_millis);
-            return;
-        } 
-        else if self.map[new_y][new_x] == EXIT && self.player.has_key == true
+            let color = match tile
+            {
+                Tile::Wall => graphics::Color::new(0.55, 0.55, 0.55, 0.9),
+                Tile::Floor => graphics::Color::new(0.2, 0.2, 0.2, 0.9),
+                Tile::Door => graphics::Color::GREEN,
+                Tile::Key => graphics::Color::YELLOW,
+                Tile::LockedDoor(colour) => colour_of(colour),
+                Tile::ColouredKey(colour) => colour_of(colour)
+            };
+            builder.rectangle(graphics::DrawMode::fill(), cell(x, y), color)?;
+        }
+        let player = &self.simulation.player;
+        builder.rectangle(graphics::DrawMode::fill(), cell(player.x, player.y), graphics::Color::WHITE)?;
+        let mesh = builder.build(ctx)?;
+        graphics::draw(ctx, &mesh, DrawParam::default())
+    }
+
+    fn draw_seed(&self, ctx: &mut Context) -> GameResult
+    {
+        let text = graphics::Text::new(format!("Seed: {}", self.simulation.settings.seed));
+        graphics::draw(ctx, &text, graphics::DrawParam::default().dest(Point2{x: 5.0, y: 5.0}))
+    }
+
+    // the run so far, under the seed
+    fn draw_hud(&self, ctx: &mut Context) -> GameResult
+    {
+        let text = graphics::Text::new(format!("{}   Score: {}   Hints: {}", stats_text(&self.simulation.stats), self.simulation.score(), self.simulation.hints_left));
+        graphics::draw(ctx, &text, graphics::DrawParam::default().dest(Point2{x: 5.0, y: 25.0}))
+    }
+
+    // the coloured keys picked up so far, under the HUD
+    fn draw_keys(&self, ctx: &mut Context) -> GameResult
+    {
+        let keys = self.simulation.player.keys;
+        if keys.is_empty()
         {
-            self.game_state = State::Credits(String::from("You found the exit!"));
-            return;
+            return Ok(());
         }
-        else if self.map[new_y][new_x] == EXIT && self.player.has_key == false
+        let mut text = graphics::Text::new("Keys:");
+        for colour in keys.iter()
         {
-            is_on_exit = true;
+            text.add(graphics::TextFragment::new(format!(" {:?}", colour)).color(colour_of(colour)));
         }
-        else if self.map[new_y][new_x] == KEY
+        graphics::draw(ctx, &text, graphics::DrawParam::default().dest(Point2{x: 5.0, y: 45.0}))
+    }
+
+    fn draw_debug(&self, ctx: &mut Context, bot: &Bot) -> GameResult
+    {
+        if let Some((x, y)) = bot.last_seen
         {
-            self.player.has_key = true;
+            let rectangle = graphics::Rect::new((x as i32 * CELL_SIZE) as f32, (y as i32 * CELL_SIZE) as f32, CELL_SIZE as f32, CELL_SIZE as f32);
+            let marker = graphics::Mesh::new_rectangle(ctx, graphics::DrawMode::stroke(3.0), rectangle, graphics::Color::RED)?;
+            graphics::draw(ctx, &marker, DrawParam::default())?;
         }
-        
-        match self.player.is_on_exit
+
+        for (x, y) in &bot.route
         {
-            true => {self.map[self.player.y][self.player.x] = EXIT;},
-            false => {self.map[self.player.y][self.player.x] = FLOOR;}
+            let center = Point2{x: (*x as i32 * CELL_SIZE + CELL_SIZE/2) as f32, y: (*y as i32 * CELL_SIZE + CELL_SIZE/2) as f32};
+            let waypoint = graphics::Mesh::new_circle(ctx, graphics::DrawMode::fill(), center, 5.0, 0.5, graphics::Color::YELLOW)?;
+            graphics::draw(ctx, &waypoint, DrawParam::default())?;
         }
-        self.player.update(new_x, new_y, is_on_exit);
-        self.map[self.player.y][self.player.x] = PLAYER;
+
+        let text = graphics::Text::new(format!("{:?}", bot.state));
+        let top_left = Point2 {
+            x: (bot.x as i32 * CELL_SIZE) as f32,
+            y: (bot.y as i32 * CELL_SIZE) as f32 - text.height(ctx),
+        };
+        graphics::draw(ctx, &text, DrawParam::default().dest(top_left))
     }
+}
 
-    pub fn update_bot_position(&mut self, new_x: usize, new_y: usize)
+fn draw_tile(ctx: &mut Context, assets: &Assets, tile: Tile, x_sq: i32, y_sq: i32) -> GameResult
+{
+    let draw_param = DrawParam::new().dest(Point2{x:x_sq as f32, y:y_sq as f32});
+    match tile
     {
-        let mut is_on_exit = false;
-        let mut is_on_key = false;
-
-        if self.map[new_y][new_x] == WALL 
+        Tile::Wall =>
         {
-            self.ai.update_direction(false);
-            return;
-        } 
-        else if self.map[new_y][new_x] == PLAYER
+            graphics::draw(ctx, &assets.wall, draw_param)?;
+        }
+        Tile::Floor =>
         {
-            self.game_state = State::Credits(String::from("Game Over!"));
-            let ten_millis = time::Duration::from_secs_f32(0.5);
-            thread::sleep(ten_millis);
-            return;
-        } 
-        else if self.map[new_y][new_x] == EXIT
+            graphics::draw(ctx, &assets.floor, draw_param)?;
+        }
+        Tile::Door =>
         {
-            is_on_exit = true;
+            graphics::draw(ctx, &assets.floor, draw_param)?;
+            graphics::draw(ctx, &assets.door, draw_param)?;
         }
-        else if self.map[new_y][new_x] == KEY
+        Tile::Key =>
         {
-            is_on_key = true;
+            graphics::draw(ctx, &assets.floor, draw_param)?;
+            graphics::draw(ctx, &assets.key, draw_param)?;
         }
-
-        match (self.ai.is_on_exit, self.ai.is_on_key)
+        Tile::LockedDoor(colour) =>
         {
-            (false, true) => {self.map[self.ai.y][self.ai.x] = KEY;},
-            (true, false) => {self.map[self.ai.y][self.ai.x] = EXIT;},
-            (false, false) => {self.map[self.ai.y][self.ai.x] = FLOOR;},
-            _ => ()
+            graphics::draw(ctx, &assets.floor, draw_param)?;
+            graphics::draw(ctx, &assets.door, draw_param.color(colour_of(colour)))?;
+        }
+        Tile::ColouredKey(colour) =>
+        {
+            graphics::draw(ctx, &assets.floor, draw_param)?;
+            graphics::draw(ctx, &assets.key, draw_param.color(colour_of(colour)))?;
         }
-        self.ai.update_position(new_x, new_y, is_on_exit, is_on_key);
-        self.map[self.ai.y][self.ai.x] = BOT;
     }
+    Ok(())
+}
 
-    pub fn restart_timer(&mut self)
+fn stats_text(stats: &Stats) -> String
+{
+    let key_time = match stats.key_time
     {
-        self.time_until_bot_speed_up = 1.0;
+        Some(key_time) => format!("   Key: {:.1} s", key_time),
+        None => String::new()
+    };
+    format!("Time: {:.1} s   Steps: {}{}", stats.elapsed, stats.steps, key_time)
+}
+
+// the sprites of locked doors and coloured keys are tinted with this
+fn colour_of(colour: Colour) -> graphics::Color
+{
+    match colour
+    {
+        Colour::Red => graphics::Color::new(1.0, 0.3, 0.3, 1.0),
+        Colour::Green => graphics::Color::new(0.3, 1.0, 0.3, 1.0),
+        Colour::Blue => graphics::Color::new(0.4, 0.5, 1.0, 1.0),
+        Colour::Purple => graphics::Color::new(0.8, 0.4, 1.0, 1.0)
     }
 }
 
-fn generate_location(map: &Vec<Vec<char>>) -> Point2<usize>
+fn draw_player(ctx: &mut Context, assets: &Assets, player: &Player) -> GameResult
+{
+    let frames = assets.player_walk.frames(player.direction);
+    let progress = animation::step_progress(player.time_until_next_step, player.time_for_step);
+    let pose = animation::pose(player.previous, (player.x, player.y), progress, frames.len());
+    draw_sprite(ctx, &frames[pose.frame], &pose)
+}
+
+fn draw_bot(ctx: &mut Context, assets: &Assets, bot: &Bot) -> GameResult
+{
+    let frames = assets.bot_walk.frames(bot.direction);
+    let progress = animation::step_progress(bot.time_until_next_step, bot.time_for_step);
+    let pose = animation::pose(bot.previous, (bot.x, bot.y), progress, frames.len());
+    draw_sprite(ctx, &frames[pose.frame], &pose)
+}
+
+fn draw_sprite(ctx: &mut Context, sprite: &graphics::Image, pose: &Pose) -> GameResult
 {
-    let mut rng = rand::thread_rng();
-    let mut x = rng.gen_range(0..map[0].len());
-    let mut y = rng.gen_range(0..map.len());
-    while map[y][x] != FLOOR
+    let cell_size = CELL_SIZE as f32;
+    let draw_param = DrawParam::new().dest(Point2{x: pose.x * cell_size, y: (pose.y - pose.bob) * cell_size});
+    graphics::draw(ctx, sprite, draw_param)
+}
+
+// input mapping lives here so the simulation only ever sees directions
+fn direction_for_key(keycode: event::KeyCode) -> Option<Direction>
+{
+    match keycode
     {
-        x = rng.gen_range(0..map[0].len());
-        y = rng.gen_range(0..map.len());
+        event::KeyCode::W | event::KeyCode::Up => Some(Direction::Up),
+        event::KeyCode::S | event::KeyCode::Down => Some(Direction::Down),
+        event::KeyCode::A | event::KeyCode::Left => Some(Direction::Left),
+        event::KeyCode::D | event::KeyCode::Right => Some(Direction::Right),
+        _ => None
     }
-    Point2 { x: x, y: y }
 }
 
-impl EventHandler<ggez::GameError> for MazeGame 
+impl EventHandler<ggez::GameError> for MazeGame
 {
-    fn update(&mut self, ctx: &mut Context) -> GameResult<()> 
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()>
     {
-        if let State::Credits(s) = &self.game_state
+        if self.simulation.game_state != State::MainState
         {
             return Ok(());
         }
-        else if let State::Start = &self.game_state
-        {
-            return Ok(());
-        }
-        
+
         const DESIRED_FPS: u32 = 60;
-        while timer::check_update_time(ctx, DESIRED_FPS) 
+        while timer::check_update_time(ctx, DESIRED_FPS)
         {
-            self.ai.look_for_player(self.map.to_owned());
-            let seconds = 1.0 / (DESIRED_FPS as f32);
-            self.ai.time_until_next_step -= seconds;
-            self.time_until_bot_speed_up -= seconds;
-            if self.ai.time_until_next_step <= 0.0
+            let input = match (self.pressed.take(), self.held.last())
             {
-                let is_on_cross_road = self.ai.check_for_cross_road(self.map.to_owned());
-                if is_on_cross_road
-                {
-                    self.ai.update_direction(is_on_cross_road);
-                }
-                match self.ai.direction
-                {
-                    UP =>
-                    {
-                        self.update_bot_position(self.ai.x, self.ai.y-1);
-                    }
-                    DOWN =>
-                    {
-                        self.update_bot_position(self.ai.x, self.ai.y+1);
-                    }
-                    LEFT =>
-                    {
-                        self.update_bot_position(self.ai.x-1, self.ai.y);
-                    }
-                    RIGHT =>
-                    {
-                        self.update_bot_position(self.ai.x+1, self.ai.y);
-                    }
-                    _ => ()
-                }
-                self.ai.restart_timer();
-            }
-
-            if self.time_until_bot_speed_up <= 0.0
+                (Some(direction), _) => Input::Move(direction),
+                (None, Some(direction)) => Input::Hold(*direction),
+                (None, None) => Input::Idle
+            };
+            self.simulation.step(input, 1.0 / (DESIRED_FPS as f32));
+            if self.simulation.game_state != State::MainState
             {
-                self.ai.speed_up();
-                self.restart_timer();
+                // give the player a moment to see what caught them before the credits
+                thread::sleep(time::Duration::from_secs_f32(0.5));
+                break;
             }
         }
-        
+
+        let (target, world_size) = (self.player_center(), self.world_size());
+        self.camera.follow(target, world_size, timer::delta(ctx).as_secs_f32());
+
         Ok(())
     }
 
     fn key_down_event(&mut self,
-        ctx: &mut Context,
+        _ctx: &mut Context,
         keycode: event::KeyCode,
         _keymod: input::keyboard::KeyMods,
-        _repeat: bool) 
+        repeat: bool)
     {
-        match self.game_state
+        match self.simulation.game_state
         {
             State::Start =>
             {
-                match keycode
+                let difficulty = self.simulation.settings.difficulty;
+                match direction_for_key(keycode)
                 {
-                    event::KeyCode::Space =>  {self.game_state = State::MainState;},
+                    Some(Direction::Left) => self.simulation.select_difficulty(difficulty.easier()),
+                    Some(Direction::Right) => self.simulation.select_difficulty(difficulty.harder()),
+                    _ if keycode == event::KeyCode::Space =>
+                    {
+                        self.simulation.start();
+                        let (target, world_size) = (self.player_center(), self.world_size());
+                        self.camera.jump_to(target, world_size);
+                    }
                     _ => ()
                 }
             }
             State::MainState =>
             {
-                match keycode
+                if keycode == event::KeyCode::F1
                 {
-                    event::KeyCode::D => self.update_player_position(self.player.x+1, self.player.y, keycode),
-                    event::KeyCode::A => self.update_player_position(self.player.x-1, self.player.y, keycode),
-                    event::KeyCode::W => self.update_player_position(self.player.x, self.player.y-1, keycode),
-                    event::KeyCode::S => self.update_player_position(self.player.x, self.player.y+1, keycode),
-                    _ => ()
+                    self.show_debug = !self.show_debug;
+                }
+                else if keycode == event::KeyCode::H
+                {
+                    self.simulation.request_hint();
+                }
+                else if keycode == event::KeyCode::M
+                {
+                    self.minimap.visible = !self.minimap.visible;
+                }
+                else if matches!(keycode, event::KeyCode::Plus | event::KeyCode::Equals | event::KeyCode::NumpadAdd)
+                {
+                    self.zoom(ZOOM_STEP);
+                }
+                else if matches!(keycode, event::KeyCode::Minus | event::KeyCode::NumpadSubtract)
+                {
+                    self.zoom(1.0 / ZOOM_STEP);
+                }
+                else if let Some(direction) = direction_for_key(keycode)
+                {
+                    // the simulation keeps the player walking, repeated key events add nothing
+                    if !repeat
+                    {
+                        self.pressed = Some(direction);
+                        self.held.retain(|held| *held != direction);
+                        self.held.push(direction);
+                    }
                 }
             }
             _ => ()
         }
     }
 
-    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> 
+    fn key_up_event(&mut self,
+        _ctx: &mut Context,
+        keycode: event::KeyCode,
+        _keymod: input::keyboard::KeyMods)
+    {
+        if let Some(direction) = direction_for_key(keycode)
+        {
+            self.held.retain(|held| *held != direction);
+        }
+    }
+
+    fn mouse_wheel_event(&mut self, _ctx: &mut Context, _x: f32, y: f32)
     {
-        match &self.game_state
+        if self.simulation.game_state == State::MainState && y != 0.0
+        {
+            self.zoom(ZOOM_STEP.powf(y.signum()));
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()>
+    {
+        match &self.simulation.game_state
         {
             State::Start =>
             {
                 graphics::clear(ctx, graphics::Color::BLACK);
                 let text = graphics::Text::new("< press Space to start >");
-                
+
                 let top_left = Point2 {
                     x: (self.conf.window_mode.width - text.width(ctx) - 5.0) / 2.0,
                     y: (self.conf.window_mode.height - text.height(ctx) - 5.0) / 2.0,
                 };
                 graphics::draw(ctx, &text, graphics::DrawParam::default().dest(top_left))?;
+
+                let difficulty = graphics::Text::new(format!("< {:?} >", self.simulation.settings.difficulty));
+                let below = Point2 {
+                    x: (self.conf.window_mode.width - difficulty.width(ctx) - 5.0) / 2.0,
+                    y: top_left.y + text.height(ctx) + 10.0,
+                };
+                graphics::draw(ctx, &difficulty, graphics::DrawParam::default().dest(below))?;
+                self.draw_seed(ctx)?;
             },
-            State::MainState => 
+            State::MainState =>
             {
-                for (y, row) in self.map.iter().enumerate()
+                graphics::clear(ctx, graphics::Color::BLACK);
+                self.draw_world(ctx)?;
+                if self.minimap.visible
                 {
-                    for (x, cell) in row.iter().enumerate()
-                    {
-                        let x_sq = x as i32 * CELL_SIZE;
-                        let y_sq = y as i32 * CELL_SIZE;
-
-                        match *cell
-                        {
-                            WALL =>
-                            {
-                                let draw_param = DrawParam::new().dest(Point2{x:x_sq as f32, y:y_sq as f32});
-                                graphics::draw(ctx, &self.assets.wall, draw_param)?;
-                            }
-                            FLOOR =>
-                            {
-                                let draw_param = DrawParam::new().dest(Point2{x:x_sq as f32, y:y_sq as f32});
-                                graphics::draw(ctx, &self.assets.floor, draw_param)?;
-                            }
-                            PLAYER =>
-                            {
-                                self.player.draw(ctx, &self.assets, x_sq, y_sq)?;
-                            }
-                            EXIT =>
-                            {
-                                let draw_param = DrawParam::new().dest(Point2{x:x_sq as f32, y:y_sq as f32});
-                                graphics::draw(ctx, &self.assets.floor, draw_param)?;
-                                graphics::draw(ctx, &self.assets.door, draw_param)?;
-                            }
-                            BOT =>
-                            {
-                                self.ai.draw(ctx, &self.assets, x_sq, y_sq)?;
-                            }
-                            KEY =>
-                            {
-                                let draw_param = DrawParam::new().dest(Point2{x:x_sq as f32, y:y_sq as f32});
-                                graphics::draw(ctx, &self.assets.floor, draw_param)?;
-                                graphics::draw(ctx, &self.assets.key, draw_param)?;
-                            }
-                            _ => {continue;}
-                        }
-                    }
+                    self.draw_minimap(ctx)?;
                 }
+                self.draw_seed(ctx)?;
+                self.draw_hud(ctx)?;
+                self.draw_keys(ctx)?;
             },
-            State::Credits(s) => 
+            State::Credits(s) =>
             {
                 graphics::clear(ctx, graphics::Color::BLACK);
                 let text = graphics::Text::new(s.to_owned());
@@ -339,9 +500,16 @@ impl EventHandler<ggez::GameError> for MazeGame
                     y: (self.conf.window_mode.height - text.height(ctx) - 5.0) / 2.0,
                 };
                 graphics::draw(ctx, &text, graphics::DrawParam::default().dest(top_left))?;
+
+                let summary = graphics::Text::new(format!("{}   Score: {}", stats_text(&self.simulation.stats), self.simulation.score()));
+                let below = Point2 {
+                    x: (self.conf.window_mode.width - summary.width(ctx) - 5.0) / 2.0,
+                    y: top_left.y + text.height(ctx) + 10.0,
+                };
+                graphics::draw(ctx, &summary, graphics::DrawParam::default().dest(below))?;
             }
         }
         graphics::present(ctx)?;
         Ok(())
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,227 @@
+use std::ops::{Index, IndexMut};
+use crate::keys::Colour;
+
+// Static terrain only, the player and the bots keep their own positions and are never written into the grid.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Tile
+{
+    Floor,
+    Wall,
+    // the exit, opened with the key
+    Door,
+    Key,
+    // a door in the middle of the maze, only a key of its colour opens it
+    LockedDoor(Colour),
+    ColouredKey(Colour)
+}
+
+impl Tile
+{
+    pub fn is_walkable(&self) -> bool
+    {
+        !matches!(self, Tile::Wall | Tile::LockedDoor(_))
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Grid<T>
+{
+    width: usize,
+    height: usize,
+    cells: Vec<T>
+}
+
+impl<T: Clone> Grid<T>
+{
+    pub fn new(width: usize, height: usize, value: T) -> Self
+    {
+        Grid{width: width, height: height, cells: vec![value; width*height]}
+    }
+}
+
+impl<T> Grid<T>
+{
+    pub fn width(&self) -> usize
+    {
+        self.width
+    }
+
+    pub fn height(&self) -> usize
+    {
+        self.height
+    }
+
+    pub fn in_bounds(&self, x: usize, y: usize) -> bool
+    {
+        x < self.width && y < self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<&T>
+    {
+        if self.in_bounds(x, y)
+        {
+            Some(&self.cells[(self.width*y) + x])
+        }
+        else
+        {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T>
+    {
+        if self.in_bounds(x, y)
+        {
+            Some(&mut self.cells[(self.width*y) + x])
+        }
+        else
+        {
+            None
+        }
+    }
+
+    // returns false and leaves the grid untouched when the position is out of bounds
+    pub fn set(&mut self, x: usize, y: usize, value: T) -> bool
+    {
+        match self.get_mut(x, y)
+        {
+            Some(cell) =>
+            {
+                *cell = value;
+                true
+            },
+            None => false
+        }
+    }
+
+    // the in bounds neighbors above, below, left and right of a position, in that order
+    pub fn neighbors(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> + '_
+    {
+        let candidates = [
+            (Some(x), y.checked_sub(1)),
+            (Some(x), y.checked_add(1)),
+            (x.checked_sub(1), Some(y)),
+            (x.checked_add(1), Some(y))
+        ];
+        candidates.into_iter().filter_map(move |candidate| match candidate
+        {
+            (Some(x), Some(y)) if self.in_bounds(x, y) => Some((x, y)),
+            _ => None
+        })
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> + '_
+    {
+        (0..self.height).flat_map(move |y| (0..self.width).map(move |x| (x, y)))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> + '_
+    {
+        self.cells.iter().enumerate().map(move |(index, cell)| ((index % self.width, index / self.width), cell))
+    }
+}
+
+impl Grid<Tile>
+{
+    pub fn from_chars(map: &[Vec<char>]) -> Self
+    {
+        let height = map.len();
+        let width = map.first().map_or(0, |row| row.len());
+        let mut grid = Grid::new(width, height, Tile::Wall);
+        for (y, row) in map.iter().enumerate()
+        {
+            for (x, cell) in row.iter().enumerate()
+            {
+                let tile = match *cell
+                {
+                    'W' => Tile::Wall,
+                    'V' => Tile::Door,
+                    'K' => Tile::Key,
+                    // locked doors are the colour's initial, their keys its number
+                    'r' => Tile::LockedDoor(Colour::Red),
+                    'g' => Tile::LockedDoor(Colour::Green),
+                    'b' => Tile::LockedDoor(Colour::Blue),
+                    'p' => Tile::LockedDoor(Colour::Purple),
+                    '1' => Tile::ColouredKey(Colour::Red),
+                    '2' => Tile::ColouredKey(Colour::Green),
+                    '3' => Tile::ColouredKey(Colour::Blue),
+                    '4' => Tile::ColouredKey(Colour::Purple),
+                    _ => Tile::Floor
+                };
+                grid.set(x, y, tile);
+            }
+        }
+        grid
+    }
+
+    pub fn is_walkable(&self, x: usize, y: usize) -> bool
+    {
+        self.get(x, y).is_some_and(|tile| tile.is_walkable())
+    }
+
+    pub fn walkable_neighbors(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> + '_
+    {
+        self.neighbors(x, y).filter(move |(x, y)| self.is_walkable(*x, *y))
+    }
+
+    // walkable tiles with a single way out
+    pub fn dead_ends(&self) -> impl Iterator<Item = (usize, usize)> + '_
+    {
+        self.positions().filter(move |(x, y)| self.is_walkable(*x, *y) && self.walkable_neighbors(*x, *y).count() == 1)
+    }
+
+    // walks a Bresenham line between the two positions, any wall or locked door strictly between them blocks the sight
+    pub fn has_line_of_sight(&self, from: (usize, usize), to: (usize, usize)) -> bool
+    {
+        if !self.in_bounds(from.0, from.1) || !self.in_bounds(to.0, to.1)
+        {
+            return false;
+        }
+
+        let (mut x, mut y) = (from.0 as isize, from.1 as isize);
+        let (target_x, target_y) = (to.0 as isize, to.1 as isize);
+        let dx = (target_x - x).abs();
+        let dy = -(target_y - y).abs();
+        let step_x = if x < target_x { 1 } else { -1 };
+        let step_y = if y < target_y { 1 } else { -1 };
+        let mut error = dx + dy;
+
+        while (x, y) != (target_x, target_y)
+        {
+            let doubled = 2 * error;
+            if doubled >= dy
+            {
+                error += dy;
+                x += step_x;
+            }
+            if doubled <= dx
+            {
+                error += dx;
+                y += step_y;
+            }
+            if (x, y) != (target_x, target_y) && !self[(x as usize, y as usize)].is_walkable()
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T>
+{
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &T
+    {
+        self.get(x, y).expect("position out of the grid")
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T>
+{
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T
+    {
+        self.get_mut(x, y).expect("position out of the grid")
+    }
+}
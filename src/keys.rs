@@ -0,0 +1,155 @@
+use rand::Rng;
+use rand::rngs::StdRng;
+use crate::grid::{Grid, Tile};
+use crate::pathfinding;
+
+// Coloured keys open the locked doors of the same colour, the exit still needs the plain key.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Colour
+{
+    Red,
+    Green,
+    Blue,
+    Purple
+}
+
+impl Colour
+{
+    pub const ALL: [Colour; 4] = [Colour::Red, Colour::Green, Colour::Blue, Colour::Purple];
+
+    fn bit(&self) -> u8
+    {
+        1 << (*self as u8)
+    }
+}
+
+// the coloured keys the player carries, a key is kept after it opened its door
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Keys(u8);
+
+impl Keys
+{
+    pub fn insert(&mut self, colour: Colour)
+    {
+        self.0 |= colour.bit();
+    }
+
+    pub fn contains(&self, colour: Colour) -> bool
+    {
+        self.0 & colour.bit() != 0
+    }
+
+    pub fn is_empty(&self) -> bool
+    {
+        self.0 == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Colour> + '_
+    {
+        Colour::ALL.into_iter().filter(move |colour| self.contains(*colour))
+    }
+}
+
+// a straight piece of corridor, a door there can't be walked around on the spot
+fn is_corridor(map: &Grid<Tile>, (x, y): (usize, usize)) -> bool
+{
+    let open = |position: Option<(usize, usize)>| position.is_some_and(|(x, y)| map.is_walkable(x, y));
+    let horizontal = open(x.checked_sub(1).map(|x| (x, y))) && open(Some((x + 1, y)));
+    let vertical = open(y.checked_sub(1).map(|y| (x, y))) && open(Some((x, y + 1)));
+    map[(x, y)] == Tile::Floor && horizontal != vertical && map.walkable_neighbors(x, y).count() == 2
+}
+
+// Locks up to `count` doors in corridors along the way from `start` to `exit`, one colour each, and puts
+// every key where it can be reached with the doors before it open and its own door and the ones after it
+// still locked. Collecting the keys in colour order always works and no key ends up behind its own door.
+// Returns the number of doors placed, fewer when the maze has no room for more.
+pub fn place_locked_doors(map: &mut Grid<Tile>, start: (usize, usize), exit: (usize, usize), count: usize, occupied: &[(usize, usize)], rng: &mut StdRng) -> usize
+{
+    let count = count.min(Colour::ALL.len());
+    if count == 0
+    {
+        return 0;
+    }
+    let path = match pathfinding::find_path(map, start, None, exit, 0)
+    {
+        Some(path) => path,
+        None => return 0
+    };
+
+    // spread evenly between the start and the exit, each on the first free corridor tile from its share of the way on
+    let mut doors: Vec<usize> = Vec::new();
+    for door in 0..count
+    {
+        let from = (door + 1) * path.len() / (count + 1);
+        let found = (from..path.len()).find(|index| !doors.contains(index) && !occupied.contains(&path[*index]) && is_corridor(map, path[*index]));
+        if let Some(index) = found
+        {
+            doors.push(index);
+        }
+    }
+    doors.sort();
+    let doors: Vec<(usize, usize)> = doors.into_iter().map(|index| path[index]).collect();
+
+    let mut taken: Vec<(usize, usize)> = occupied.iter().chain(doors.iter()).copied().collect();
+    let mut keys = Vec::new();
+    for door in 0..doors.len()
+    {
+        let mut locked = map.clone();
+        for (later, colour) in doors.iter().zip(Colour::ALL).skip(door)
+        {
+            locked.set(later.0, later.1, Tile::LockedDoor(colour));
+        }
+        let reachable = pathfinding::distances(&locked, start);
+        let candidates: Vec<(usize, usize)> = locked.positions().filter(|position| locked[*position] == Tile::Floor && reachable[*position].is_some() && !taken.contains(position)).collect();
+        if candidates.is_empty()
+        {
+            break;
+        }
+        let key = candidates[rng.gen_range(0..candidates.len())];
+        taken.push(key);
+        keys.push(key);
+    }
+
+    for ((door, key), colour) in doors.iter().zip(keys.iter()).zip(Colour::ALL)
+    {
+        map.set(door.0, door.1, Tile::LockedDoor(colour));
+        map.set(key.0, key.1, Tile::ColouredKey(colour));
+    }
+    keys.len()
+}
+
+// whether a player walking from `start` can pick up every coloured key and open every locked door, in some order
+pub fn can_collect_all(map: &Grid<Tile>, start: (usize, usize)) -> bool
+{
+    let mut map = map.clone();
+    let mut keys = Keys::default();
+    loop
+    {
+        let reachable = pathfinding::distances(&map, start);
+        let mut progress = false;
+        for (x, y) in map.positions().collect::<Vec<_>>()
+        {
+            match map[(x, y)]
+            {
+                Tile::ColouredKey(colour) if reachable[(x, y)].is_some() =>
+                {
+                    keys.insert(colour);
+                    map.set(x, y, Tile::Floor);
+                    progress = true;
+                }
+                Tile::LockedDoor(colour) if keys.contains(colour) && map.neighbors(x, y).any(|next| reachable[next].is_some()) =>
+                {
+                    map.set(x, y, Tile::Floor);
+                    progress = true;
+                }
+                _ => ()
+            }
+        }
+        if !progress
+        {
+            break;
+        }
+    }
+    let locked = map.iter().any(|(_, tile)| matches!(tile, Tile::ColouredKey(_) | Tile::LockedDoor(_)));
+    !locked
+}
@@ -1,5 +1,18 @@
 pub mod bot;
+pub mod brain;
 pub mod player;
+pub mod direction;
+pub mod grid;
+pub mod keys;
+pub mod pathfinding;
+pub mod solver;
+pub mod visibility;
 pub mod maze_generator;
+pub mod maze_algorithms;
+pub mod animation;
 pub mod assets;
+pub mod camera;
+pub mod minimap;
+pub mod difficulty;
+pub mod simulation;
 pub mod game;
\ No newline at end of file
@@ -4,14 +4,50 @@ use ggez::{
 };
 use std::path;
 use std::env;
+use rand::Rng;
 use Maze::game;
+use Maze::maze_algorithms::Algorithm;
+use Maze::bot::BotConfig;
+use Maze::difficulty::Difficulty;
+use Maze::minimap::Minimap;
+use Maze::visibility::Sight;
 
 fn main() -> GameResult
 {
+    let args: Vec<String> = env::args().collect();
+    let mut settings = game::Settings::with_seed(parse_seed(&args).unwrap_or_else(|| rand::thread_rng().gen()));
+    settings.algorithm = parse_algorithm(&args).unwrap_or_default();
+    if let Some(loop_density) = parse_loop_density(&args)
+    {
+        settings.loop_density = loop_density;
+    }
+    if let Some(difficulty) = parse_difficulty(&args)
+    {
+        settings.difficulty = difficulty;
+    }
+    if let Some(bots) = parse_bots(&args)
+    {
+        settings.bots = bots;
+    }
+    if let Some(locked_doors) = parse_locked_doors(&args)
+    {
+        settings.locked_doors = locked_doors;
+    }
+    if let Some(fog) = parse_fog(&args)
+    {
+        settings.fog = Some(fog);
+    }
+    if let Some((width, height)) = parse_size(&args)
+    {
+        settings.width = width;
+        settings.height = height;
+    }
+
+    let (window_width, window_height) = game::window_size(&settings);
     let mut conf = Conf::new().
         window_mode(WindowMode {
-            width: 945.0,
-            height: 945.0,
+            width: window_width,
+            height: window_height,
             ..Default::default()
         });
     conf.window_setup.title = String::from("Maze");
@@ -28,7 +64,148 @@ fn main() -> GameResult
         filesystem::mount(&mut ctx, &path, true);
     }
         
-    let game = game::MazeGame::new(&mut ctx, conf)?;
+    let mut game = game::MazeGame::new(&mut ctx, conf, settings)?;
+    if let Some(minimap) = parse_minimap(&args)
+    {
+        game.minimap = minimap;
+    }
 
     event::run(ctx, event_loop, game)
 }
+
+fn argument_value<'a>(args: &'a [String], name: &str) -> Option<&'a String>
+{
+    let position = args.iter().position(|arg| arg == name)?;
+    args.get(position + 1)
+}
+
+// accepts `--seed <number>` so a maze reported by a tester can be replayed
+fn parse_seed(args: &[String]) -> Option<u64>
+{
+    match argument_value(args, "--seed")?.parse::<u64>()
+    {
+        Ok(seed) => Some(seed),
+        Err(_) =>
+        {
+            eprintln!("--seed expects a non-negative integer, using a random seed");
+            None
+        }
+    }
+}
+
+// accepts `--algorithm <name>`, e.g. `prim`, `kruskal` or `hunt-and-kill`
+fn parse_algorithm(args: &[String]) -> Option<Algorithm>
+{
+    match argument_value(args, "--algorithm")?.parse::<Algorithm>()
+    {
+        Ok(algorithm) => Some(algorithm),
+        Err(error) =>
+        {
+            eprintln!("{}, using the recursive backtracker", error);
+            None
+        }
+    }
+}
+
+// accepts `--size <width>x<height>` in maze cells, e.g. `--size 20x12`
+fn parse_size(args: &[String]) -> Option<(usize, usize)>
+{
+    let value = argument_value(args, "--size")?;
+    let size = value.split_once('x').and_then(|(width, height)| Some((width.parse::<usize>().ok()?, height.parse::<usize>().ok()?)));
+    match size
+    {
+        // anything smaller has no room left for the bot and the key next to the player and the exit
+        Some((width, height)) if width > 1 && height > 1 => Some((width, height)),
+        _ =>
+        {
+            eprintln!("--size expects <width>x<height> of at least 2x2, using the default size");
+            None
+        }
+    }
+}
+
+// accepts `--loops <density>` between 0.0 (perfect maze) and 1.0 (no dead ends)
+fn parse_loop_density(args: &[String]) -> Option<f64>
+{
+    match argument_value(args, "--loops")?.parse::<f64>()
+    {
+        Ok(loop_density) if (0.0..=1.0).contains(&loop_density) => Some(loop_density),
+        _ =>
+        {
+            eprintln!("--loops expects a number between 0.0 and 1.0, using the default loop density");
+            None
+        }
+    }
+}
+
+// accepts `--bot <profile>[:<seconds per step>]`, separated by commas for more than one bot,
+// e.g. `--bot hunter` or `--bot wanderer,guard,ambusher:0.15`
+fn parse_bots(args: &[String]) -> Option<Vec<BotConfig>>
+{
+    let bots: Result<Vec<BotConfig>, String> = argument_value(args, "--bot")?.split(',').map(|bot| bot.trim().parse::<BotConfig>()).collect();
+    match bots
+    {
+        Ok(bots) => Some(bots),
+        Err(error) =>
+        {
+            eprintln!("{}, using the bots of the difficulty", error);
+            None
+        }
+    }
+}
+
+// accepts `--difficulty easy|normal|hard|nightmare`, it can still be changed on the start screen
+fn parse_difficulty(args: &[String]) -> Option<Difficulty>
+{
+    match argument_value(args, "--difficulty")?.parse::<Difficulty>()
+    {
+        Ok(difficulty) => Some(difficulty),
+        Err(error) =>
+        {
+            eprintln!("{}, using normal", error);
+            None
+        }
+    }
+}
+
+// accepts `--minimap <corner>[:<size in pixels>]`, e.g. `--minimap bottom-left:240`
+fn parse_minimap(args: &[String]) -> Option<Minimap>
+{
+    match argument_value(args, "--minimap")?.parse::<Minimap>()
+    {
+        Ok(minimap) => Some(minimap),
+        Err(error) =>
+        {
+            eprintln!("{}, using the top right corner", error);
+            None
+        }
+    }
+}
+
+// accepts `--fog radius[:<tiles>]` or `--fog sight[:<tiles>]` to only show what the player sees
+fn parse_fog(args: &[String]) -> Option<Sight>
+{
+    match argument_value(args, "--fog")?.parse::<Sight>()
+    {
+        Ok(sight) => Some(sight),
+        Err(error) =>
+        {
+            eprintln!("{}, playing without fog of war", error);
+            None
+        }
+    }
+}
+
+// accepts `--doors <count>` for up to 4 locked doors, each opened by the key of its colour
+fn parse_locked_doors(args: &[String]) -> Option<usize>
+{
+    match argument_value(args, "--doors")?.parse::<usize>()
+    {
+        Ok(locked_doors) if locked_doors <= 4 => Some(locked_doors),
+        _ =>
+        {
+            eprintln!("--doors expects a number from 0 to 4, playing without locked doors");
+            None
+        }
+    }
+}
@@ -0,0 +1,488 @@
+use std::str::FromStr;
+use rand::Rng;
+use rand::seq::SliceRandom;
+use crate::maze_generator::Graph;
+
+// Every algorithm carves passages into the walls of an untouched `Graph` and marks the cells
+// it reaches as visited, so `draw_maze` and the game do not care which one produced the maze.
+// All randomness comes from `graph.rng`, which keeps the result reproducible from the seed.
+pub trait MazeAlgorithm
+{
+    fn generate(&self, graph: &mut Graph);
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum Algorithm
+{
+    #[default]
+    RecursiveBacktracker,
+    Prim,
+    Kruskal,
+    Wilson,
+    Eller,
+    AldousBroder,
+    HuntAndKill,
+    Sidewinder,
+    BinaryTree,
+    GrowingTree
+}
+
+impl Algorithm
+{
+    pub const ALL: [Algorithm; 10] = [
+        Algorithm::RecursiveBacktracker,
+        Algorithm::Prim,
+        Algorithm::Kruskal,
+        Algorithm::Wilson,
+        Algorithm::Eller,
+        Algorithm::AldousBroder,
+        Algorithm::HuntAndKill,
+        Algorithm::Sidewinder,
+        Algorithm::BinaryTree,
+        Algorithm::GrowingTree
+    ];
+
+    pub fn generator(&self) -> Box<dyn MazeAlgorithm>
+    {
+        match self
+        {
+            Algorithm::RecursiveBacktracker => Box::new(RecursiveBacktracker),
+            Algorithm::Prim => Box::new(Prim),
+            Algorithm::Kruskal => Box::new(Kruskal),
+            Algorithm::Wilson => Box::new(Wilson),
+            Algorithm::Eller => Box::new(Eller),
+            Algorithm::AldousBroder => Box::new(AldousBroder),
+            Algorithm::HuntAndKill => Box::new(HuntAndKill),
+            Algorithm::Sidewinder => Box::new(Sidewinder),
+            Algorithm::BinaryTree => Box::new(BinaryTree),
+            Algorithm::GrowingTree => Box::new(GrowingTree{newest_ratio: 0.5})
+        }
+    }
+
+    pub fn generate(&self, graph: &mut Graph)
+    {
+        self.generator().generate(graph);
+    }
+}
+
+impl FromStr for Algorithm
+{
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err>
+    {
+        match name.to_lowercase().replace(['-', '_'], "").as_str()
+        {
+            "recursivebacktracker" | "backtracker" => Ok(Algorithm::RecursiveBacktracker),
+            "prim" => Ok(Algorithm::Prim),
+            "kruskal" => Ok(Algorithm::Kruskal),
+            "wilson" => Ok(Algorithm::Wilson),
+            "eller" => Ok(Algorithm::Eller),
+            "aldousbroder" => Ok(Algorithm::AldousBroder),
+            "huntandkill" => Ok(Algorithm::HuntAndKill),
+            "sidewinder" => Ok(Algorithm::Sidewinder),
+            "binarytree" => Ok(Algorithm::BinaryTree),
+            "growingtree" => Ok(Algorithm::GrowingTree),
+            _ => Err(format!("unknown maze algorithm `{}`", name))
+        }
+    }
+}
+
+pub struct RecursiveBacktracker;
+
+impl MazeAlgorithm for RecursiveBacktracker
+{
+    fn generate(&self, graph: &mut Graph)
+    {
+        graph.generate_maze(0, 0);
+    }
+}
+
+pub struct Prim;
+
+impl MazeAlgorithm for Prim
+{
+    fn generate(&self, graph: &mut Graph)
+    {
+        let mut in_frontier = vec![false; graph.width*graph.height];
+        let mut frontier: Vec<(usize, usize)> = Vec::new();
+
+        let start = random_cell(graph);
+        add_to_maze(graph, start, &mut frontier, &mut in_frontier);
+
+        while !frontier.is_empty()
+        {
+            let random_number = graph.rng.gen_range(0..frontier.len());
+            let cell = frontier.swap_remove(random_number);
+            let in_maze: Vec<(usize, usize)> = graph.neighbors(cell.0, cell.1).into_iter().filter(|(x, y)| graph.is_visited(*x, *y)).collect();
+            let random_number = graph.rng.gen_range(0..in_maze.len());
+            graph.remove_wall_between(cell, in_maze[random_number]);
+            add_to_maze(graph, cell, &mut frontier, &mut in_frontier);
+        }
+    }
+}
+
+fn add_to_maze(graph: &mut Graph, (x, y): (usize, usize), frontier: &mut Vec<(usize, usize)>, in_frontier: &mut [bool])
+{
+    graph.mark_visited(x, y);
+    for (next_x, next_y) in graph.neighbors(x, y)
+    {
+        let index = graph.index(next_x, next_y);
+        if !graph.is_visited(next_x, next_y) && !in_frontier[index]
+        {
+            in_frontier[index] = true;
+            frontier.push((next_x, next_y));
+        }
+    }
+}
+
+pub struct Kruskal;
+
+impl MazeAlgorithm for Kruskal
+{
+    fn generate(&self, graph: &mut Graph)
+    {
+        let mut edges: Vec<((usize, usize), (usize, usize))> = Vec::new();
+        for y in 0..graph.height
+        {
+            for x in 0..graph.width
+            {
+                if x < graph.width - 1
+                {
+                    edges.push(((x, y), (x+1, y)));
+                }
+                if y < graph.height - 1
+                {
+                    edges.push(((x, y), (x, y+1)));
+                }
+            }
+        }
+        edges.shuffle(&mut graph.rng);
+
+        let mut parents: Vec<usize> = (0..graph.width*graph.height).collect();
+        for (first, second) in edges
+        {
+            let first_root = find_root(&mut parents, graph.index(first.0, first.1));
+            let second_root = find_root(&mut parents, graph.index(second.0, second.1));
+            if first_root != second_root
+            {
+                parents[first_root] = second_root;
+                graph.remove_wall_between(first, second);
+            }
+        }
+        graph.visited.iter_mut().for_each(|visited| *visited = true);
+    }
+}
+
+fn find_root(parents: &mut [usize], mut index: usize) -> usize
+{
+    while parents[index] != index
+    {
+        parents[index] = parents[parents[index]];
+        index = parents[index];
+    }
+    index
+}
+
+pub struct Wilson;
+
+impl MazeAlgorithm for Wilson
+{
+    fn generate(&self, graph: &mut Graph)
+    {
+        let start = random_cell(graph);
+        graph.mark_visited(start.0, start.1);
+
+        // for every cell of the current walk remember where the walk left it last,
+        // following those exits from the walk's start gives the loop erased path
+        let mut exits: Vec<Option<(usize, usize)>> = vec![None; graph.width*graph.height];
+        for y in 0..graph.height
+        {
+            for x in 0..graph.width
+            {
+                if graph.is_visited(x, y)
+                {
+                    continue;
+                }
+
+                let mut current = (x, y);
+                while !graph.is_visited(current.0, current.1)
+                {
+                    let next = random_neighbor(graph, current);
+                    let index = graph.index(current.0, current.1);
+                    exits[index] = Some(next);
+                    current = next;
+                }
+
+                let mut current = (x, y);
+                while !graph.is_visited(current.0, current.1)
+                {
+                    let next = exits[graph.index(current.0, current.1)].unwrap();
+                    graph.mark_visited(current.0, current.1);
+                    graph.remove_wall_between(current, next);
+                    current = next;
+                }
+            }
+        }
+    }
+}
+
+pub struct Eller;
+
+impl MazeAlgorithm for Eller
+{
+    fn generate(&self, graph: &mut Graph)
+    {
+        // sets[x] is the set of the cell in column x of the current row, 0 means not assigned yet
+        let mut sets: Vec<usize> = vec![0; graph.width];
+        let mut next_set = 1;
+
+        for y in 0..graph.height
+        {
+            let last_row = y == graph.height - 1;
+            for set in sets.iter_mut()
+            {
+                if *set == 0
+                {
+                    *set = next_set;
+                    next_set += 1;
+                }
+            }
+
+            for x in 0..graph.width - 1
+            {
+                if sets[x] != sets[x+1] && (last_row || graph.rng.gen_bool(0.5))
+                {
+                    graph.remove_wall_between((x, y), (x+1, y));
+                    let merged = sets[x+1];
+                    let kept = sets[x];
+                    sets.iter_mut().filter(|set| **set == merged).for_each(|set| *set = kept);
+                }
+            }
+
+            if last_row
+            {
+                break;
+            }
+
+            // every set needs at least one passage down or it would be cut off from the rest
+            let mut next_sets: Vec<usize> = vec![0; graph.width];
+            let mut distinct: Vec<usize> = sets.clone();
+            distinct.sort_unstable();
+            distinct.dedup();
+            for set in distinct
+            {
+                let mut members: Vec<usize> = (0..graph.width).filter(|x| sets[*x] == set).collect();
+                members.shuffle(&mut graph.rng);
+                let count = graph.rng.gen_range(1..=members.len());
+                for x in members.into_iter().take(count)
+                {
+                    graph.remove_wall_between((x, y), (x, y+1));
+                    next_sets[x] = set;
+                }
+            }
+            sets = next_sets;
+        }
+        graph.visited.iter_mut().for_each(|visited| *visited = true);
+    }
+}
+
+pub struct AldousBroder;
+
+impl MazeAlgorithm for AldousBroder
+{
+    fn generate(&self, graph: &mut Graph)
+    {
+        let mut current = random_cell(graph);
+        graph.mark_visited(current.0, current.1);
+        let mut remaining = graph.width*graph.height - 1;
+
+        while remaining > 0
+        {
+            let next = random_neighbor(graph, current);
+            if !graph.is_visited(next.0, next.1)
+            {
+                graph.remove_wall_between(current, next);
+                graph.mark_visited(next.0, next.1);
+                remaining -= 1;
+            }
+            current = next;
+        }
+    }
+}
+
+pub struct HuntAndKill;
+
+impl MazeAlgorithm for HuntAndKill
+{
+    fn generate(&self, graph: &mut Graph)
+    {
+        let mut current = Some(random_cell(graph));
+        // rows above this one are known to be fully visited, so the hunt never rescans them
+        let mut hunt_from = 0;
+
+        while let Some(cell) = current
+        {
+            graph.mark_visited(cell.0, cell.1);
+            let unvisited: Vec<(usize, usize)> = graph.neighbors(cell.0, cell.1).into_iter().filter(|(x, y)| !graph.is_visited(*x, *y)).collect();
+            if unvisited.is_empty()
+            {
+                current = hunt(graph, &mut hunt_from);
+            }
+            else
+            {
+                let random_number = graph.rng.gen_range(0..unvisited.len());
+                graph.remove_wall_between(cell, unvisited[random_number]);
+                current = Some(unvisited[random_number]);
+            }
+        }
+    }
+}
+
+fn hunt(graph: &mut Graph, hunt_from: &mut usize) -> Option<(usize, usize)>
+{
+    for y in *hunt_from..graph.height
+    {
+        let mut row_done = true;
+        for x in 0..graph.width
+        {
+            if graph.is_visited(x, y)
+            {
+                continue;
+            }
+            row_done = false;
+
+            let visited: Vec<(usize, usize)> = graph.neighbors(x, y).into_iter().filter(|(x, y)| graph.is_visited(*x, *y)).collect();
+            if !visited.is_empty()
+            {
+                let random_number = graph.rng.gen_range(0..visited.len());
+                graph.remove_wall_between((x, y), visited[random_number]);
+                return Some((x, y));
+            }
+        }
+        if row_done && y == *hunt_from
+        {
+            *hunt_from += 1;
+        }
+    }
+    None
+}
+
+pub struct Sidewinder;
+
+impl MazeAlgorithm for Sidewinder
+{
+    fn generate(&self, graph: &mut Graph)
+    {
+        for y in 0..graph.height
+        {
+            let mut run_start = 0;
+            for x in 0..graph.width
+            {
+                let at_east_edge = x == graph.width - 1;
+                // the top row cannot carve up, so it is one long corridor
+                let close_run = at_east_edge || (y > 0 && graph.rng.gen_bool(0.5));
+                if close_run
+                {
+                    if y > 0
+                    {
+                        let chosen = graph.rng.gen_range(run_start..=x);
+                        graph.remove_wall_between((chosen, y), (chosen, y-1));
+                    }
+                    run_start = x + 1;
+                }
+                else
+                {
+                    graph.remove_wall_between((x, y), (x+1, y));
+                }
+            }
+        }
+        graph.visited.iter_mut().for_each(|visited| *visited = true);
+    }
+}
+
+pub struct BinaryTree;
+
+impl MazeAlgorithm for BinaryTree
+{
+    fn generate(&self, graph: &mut Graph)
+    {
+        for y in 0..graph.height
+        {
+            for x in 0..graph.width
+            {
+                let mut candidates: Vec<(usize, usize)> = Vec::new();
+                if y > 0
+                {
+                    candidates.push((x, y-1));
+                }
+                if x > 0
+                {
+                    candidates.push((x-1, y));
+                }
+                if !candidates.is_empty()
+                {
+                    let random_number = graph.rng.gen_range(0..candidates.len());
+                    graph.remove_wall_between((x, y), candidates[random_number]);
+                }
+            }
+        }
+        graph.visited.iter_mut().for_each(|visited| *visited = true);
+    }
+}
+
+// picks the newest active cell with probability `newest_ratio` and a random one otherwise,
+// 1.0 behaves like the recursive backtracker and 0.0 like Prim's
+pub struct GrowingTree
+{
+    pub newest_ratio: f64
+}
+
+impl MazeAlgorithm for GrowingTree
+{
+    fn generate(&self, graph: &mut Graph)
+    {
+        let start = random_cell(graph);
+        graph.mark_visited(start.0, start.1);
+        let mut active: Vec<(usize, usize)> = vec![start];
+
+        while !active.is_empty()
+        {
+            let index = if graph.rng.gen_bool(self.newest_ratio.clamp(0.0, 1.0))
+            {
+                active.len() - 1
+            }
+            else
+            {
+                graph.rng.gen_range(0..active.len())
+            };
+            let cell = active[index];
+
+            let unvisited: Vec<(usize, usize)> = graph.neighbors(cell.0, cell.1).into_iter().filter(|(x, y)| !graph.is_visited(*x, *y)).collect();
+            if unvisited.is_empty()
+            {
+                active.remove(index);
+            }
+            else
+            {
+                let random_number = graph.rng.gen_range(0..unvisited.len());
+                let next = unvisited[random_number];
+                graph.remove_wall_between(cell, next);
+                graph.mark_visited(next.0, next.1);
+                active.push(next);
+            }
+        }
+    }
+}
+
+fn random_cell(graph: &mut Graph) -> (usize, usize)
+{
+    (graph.rng.gen_range(0..graph.width), graph.rng.gen_range(0..graph.height))
+}
+
+fn random_neighbor(graph: &mut Graph, (x, y): (usize, usize)) -> (usize, usize)
+{
+    let neighbors = graph.neighbors(x, y);
+    let random_number = graph.rng.gen_range(0..neighbors.len());
+    neighbors[random_number]
+}
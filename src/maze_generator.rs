@@ -1,5 +1,6 @@
-use std::collections::HashSet;
-use rand::{self, Rng};
+use rand::{self, Rng, SeedableRng};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
 
 pub struct Cell {
     pub x: usize,
@@ -14,15 +15,24 @@ pub struct Graph
 {
     pub cells: Vec<Cell>,
     pub back_track: Vec<(usize, usize)>,
-    pub visited: HashSet<(usize, usize)>,
+    // indexed like `cells`, a flat vector keeps huge grids cheap to track
+    pub visited: Vec<bool>,
     // because we start of a grid 
     pub width: usize,
-    pub height: usize
+    pub height: usize,
+    // every random choice during generation is drawn from this rng so the same seed gives the same maze
+    pub seed: u64,
+    pub rng: StdRng
 }
 
 impl Graph
 {
     pub fn new(width: usize, height: usize) -> Self
+    {
+        Graph::with_seed(width, height, rand::thread_rng().gen())
+    }
+
+    pub fn with_seed(width: usize, height: usize, seed: u64) -> Self
     {
         let mut cells: Vec<Cell> = Vec::new();
         for i in 0..height
@@ -40,71 +50,133 @@ impl Graph
             }
         }
 
-        Graph{cells: cells, back_track: Vec::new(), visited: HashSet::new(), width: width, height: height}
+        Graph{cells: cells, back_track: Vec::new(), visited: vec![false; width*height], width: width, height: height, seed: seed, rng: StdRng::seed_from_u64(seed)}
     }
 
+    // recursive backtracker driven by an explicit stack, so the depth is not bounded by the thread's stack size
     pub fn generate_maze(&mut self, x: usize, y: usize)
     {
-        if !self.visited.contains(&(x, y)) 
+        let mut current = (x, y);
+        loop
         {
-            self.visited.insert((x, y));
-            self.back_track.push((x, y));
-        }
+            if !self.is_visited(current.0, current.1)
+            {
+                self.mark_visited(current.0, current.1);
+                self.back_track.push(current);
+            }
 
-        match self.get_random_neighbor(x, y)
-        {
-            Some(next_cell) =>
+            match self.get_random_neighbor(current.0, current.1)
             {
-                if next_cell.0 == x
+                Some(next_cell) =>
                 {
-                    if next_cell.1 < y
-                    {
-                        self.cells[(self.width*next_cell.1) + next_cell.0].down = false;
-                        self.cells[(self.width*y) + x].up = false;
-                    }
-                    else if next_cell.1 > y
-                    {
-                        self.cells[(self.width*next_cell.1) + next_cell.0].up = false;
-                        self.cells[(self.width*y) + x].down = false;
-                    }
-                }
-                else if next_cell.1 == y
+                    self.remove_wall_between(current, next_cell);
+                    current = next_cell;
+                },
+                None =>
                 {
-                    if next_cell.0 < x
-                    {
-                        self.cells[(self.width*next_cell.1) + next_cell.0].right = false;
-                        self.cells[(self.width*y) + x].left = false;
-                    }
-                    else if next_cell.0 > x
+                    match self.back_track.pop()
                     {
-                        self.cells[(self.width*next_cell.1) + next_cell.0].left = false;
-                        self.cells[(self.width*y) + x].right = false;
+                        Some(cell) => current = cell,
+                        None => break
                     }
                 }
-                else 
-                {
-                    panic!("Not neighbors");
-                }
-                self.generate_maze(next_cell.0, next_cell.1)
-            },
-            None => 
+            }
+        }
+    }
+
+    pub fn remove_wall_between(&mut self, (x, y): (usize, usize), next_cell: (usize, usize))
+    {
+        if next_cell.0 == x
+        {
+            if next_cell.1 < y
             {
-                match self.back_track.pop()
-                {
-                    Some(cell) =>
-                    {
-                        self.generate_maze(cell.0, cell.1);
-                        ()
-                    },
-                    None =>
-                    {
-                        ()
-                    }
-                }
+                self.cells[(self.width*next_cell.1) + next_cell.0].down = false;
+                self.cells[(self.width*y) + x].up = false;
+            }
+            else if next_cell.1 > y
+            {
+                self.cells[(self.width*next_cell.1) + next_cell.0].up = false;
+                self.cells[(self.width*y) + x].down = false;
+            }
+        }
+        else if next_cell.1 == y
+        {
+            if next_cell.0 < x
+            {
+                self.cells[(self.width*next_cell.1) + next_cell.0].right = false;
+                self.cells[(self.width*y) + x].left = false;
+            }
+            else if next_cell.0 > x
+            {
+                self.cells[(self.width*next_cell.1) + next_cell.0].left = false;
+                self.cells[(self.width*y) + x].right = false;
+            }
+        }
+        else 
+        {
+            panic!("Not neighbors");
+        }
+    }
+
+    // opens dead ends to add loops, 0.0 keeps a perfect maze and 1.0 leaves no dead end at all
+    pub fn braid(&mut self, loop_density: f64)
+    {
+        let loop_density = loop_density.clamp(0.0, 1.0);
+        let mut dead_ends: Vec<(usize, usize)> = self.cells.iter().filter(|cell| self.is_dead_end(cell.x, cell.y)).map(|cell| (cell.x, cell.y)).collect();
+        dead_ends.shuffle(&mut self.rng);
+
+        for (x, y) in dead_ends
+        {
+            // opening an earlier dead end may already have fixed this one
+            if !self.is_dead_end(x, y) || !self.rng.gen_bool(loop_density)
+            {
+                continue;
+            }
+
+            let walled: Vec<(usize, usize)> = self.neighbors(x, y).into_iter().filter(|next| self.has_wall_between((x, y), *next)).collect();
+            // joining two dead ends removes both of them with a single wall
+            let dead_end_neighbors: Vec<(usize, usize)> = walled.iter().copied().filter(|(next_x, next_y)| self.is_dead_end(*next_x, *next_y)).collect();
+            let candidates = if dead_end_neighbors.is_empty() { walled } else { dead_end_neighbors };
+            if !candidates.is_empty()
+            {
+                let random_number = self.rng.gen_range(0..candidates.len());
+                self.remove_wall_between((x, y), candidates[random_number]);
             }
         }
     }
 
+    pub fn is_dead_end(&self, x: usize, y: usize) -> bool
+    {
+        let cell = &self.cells[self.index(x, y)];
+        let openings = [cell.up, cell.down, cell.left, cell.right].iter().filter(|wall| !**wall).count();
+        openings == 1
+    }
+
+    pub fn has_wall_between(&self, (x, y): (usize, usize), (next_x, next_y): (usize, usize)) -> bool
+    {
+        let cell = &self.cells[self.index(x, y)];
+        if next_x == x && next_y + 1 == y
+        {
+            cell.up
+        }
+        else if next_x == x && next_y == y + 1
+        {
+            cell.down
+        }
+        else if next_y == y && next_x + 1 == x
+        {
+            cell.left
+        }
+        else if next_y == y && next_x == x + 1
+        {
+            cell.right
+        }
+        else
+        {
+            panic!("Not neighbors");
+        }
+    }
+
     pub fn draw_maze(&self) -> Vec<Vec<char>>
     {
         let mut maze: Vec<Vec<char>> = Vec::new();
@@ -123,7 +195,7 @@ impl Graph
             row_bellow.push('W');
             for j in 0..self.width
             {
-                let index = (i*self.height) + j;
+                let index = self.index(j, i);
                 row.push('.');
                 if self.cells[index].right == false
                 {
@@ -136,27 +208,12 @@ impl Graph
                 if self.cells[index].down == false
                 {
                     row_bellow.push('.');
-                    row_bellow.push('W');
                 }
                 else
                 {
-                    if i != self.height-1
-                    {
-                        if (j+i)%2==0
-                        {
-                            row_bellow.push('W');
-                        }
-                        else
-                        {
-                            row_bellow.push('.');
-                        }
-                    }
-                    else
-                    {
-                        row_bellow.push('W');
-                    }
                     row_bellow.push('W');
                 }
+                row_bellow.push('W');
             }
             maze.push(row);
             maze.push(row_bellow);
@@ -164,7 +221,7 @@ impl Graph
         maze
     }
 
-    fn get_random_neighbor(&self, current_x: usize, current_y: usize) -> Option<(usize, usize)>
+    fn get_random_neighbor(&mut self, current_x: usize, current_y: usize) -> Option<(usize, usize)>
     {
         let unvisited_neighbors = self.get_unvisited_neighbors(current_x, current_y);
         if unvisited_neighbors.len() == 0 
@@ -173,39 +230,51 @@ impl Graph
         } 
         else 
         {
-            let mut rng = rand::thread_rng();
-            let random_number = rng.gen_range(0..unvisited_neighbors.len());
+            let random_number = self.rng.gen_range(0..unvisited_neighbors.len());
             Some(unvisited_neighbors[random_number])
         }
     }
 
-    fn get_unvisited_neighbors(&self, current_x: usize, current_y: usize) -> Vec<(usize, usize)>
+    pub fn is_visited(&self, x: usize, y: usize) -> bool
+    {
+        self.visited[self.index(x, y)]
+    }
+
+    pub fn mark_visited(&mut self, x: usize, y: usize)
+    {
+        let index = self.index(x, y);
+        self.visited[index] = true;
+    }
+
+    pub fn index(&self, x: usize, y: usize) -> usize
     {
-        let mut unvisited: Vec<(usize, usize)> = Vec::new();
-        if current_x > 0
+        (self.width*y) + x
+    }
+
+    pub fn neighbors(&self, x: usize, y: usize) -> Vec<(usize, usize)>
+    {
+        let mut neighbors: Vec<(usize, usize)> = Vec::new();
+        if x > 0
         {
-            if !self.visited.contains(&(current_x-1, current_y)) {
-                unvisited.push((current_x-1, current_y));
-            }
+            neighbors.push((x-1, y));
         }
-        if current_x < self.width - 1
+        if x < self.width - 1
         {
-            if !self.visited.contains(&(current_x+1, current_y)) {
-                unvisited.push((current_x+1, current_y));
-            }
+            neighbors.push((x+1, y));
         }
-        if current_y > 0
+        if y > 0
         {
-            if !self.visited.contains(&(current_x, current_y-1)) {
-                unvisited.push((current_x, current_y-1));
-            }
+            neighbors.push((x, y-1));
         }
-        if current_y < self.height - 1
+        if y < self.height - 1
         {
-            if !self.visited.contains(&(current_x, current_y+1)) {
-                unvisited.push((current_x, current_y+1));
-            }
+            neighbors.push((x, y+1));
         }
-        unvisited
+        neighbors
+    }
+
+    fn get_unvisited_neighbors(&self, current_x: usize, current_y: usize) -> Vec<(usize, usize)>
+    {
+        self.neighbors(current_x, current_y).into_iter().filter(|(x, y)| !self.is_visited(*x, *y)).collect()
     }
 }
\ No newline at end of file
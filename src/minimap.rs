@@ -0,0 +1,101 @@
+use std::str::FromStr;
+use crate::grid::Tile;
+use crate::simulation::Simulation;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Corner
+{
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight
+}
+
+impl FromStr for Corner
+{
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err>
+    {
+        match name.to_lowercase().replace('_', "-").as_str()
+        {
+            "top-left" => Ok(Corner::TopLeft),
+            "top-right" => Ok(Corner::TopRight),
+            "bottom-left" => Ok(Corner::BottomLeft),
+            "bottom-right" => Ok(Corner::BottomRight),
+            _ => Err(format!("unknown corner `{}`", name))
+        }
+    }
+}
+
+// A scaled down copy of the part of the maze the player has already seen, drawn over the window.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Minimap
+{
+    pub visible: bool,
+    pub corner: Corner,
+    // the longer side of the minimap in pixels, the shorter one follows the shape of the maze
+    pub size: f32,
+    // distance to the edges of the window
+    pub margin: f32
+}
+
+impl Default for Minimap
+{
+    fn default() -> Self
+    {
+        Minimap{visible: true, corner: Corner::TopRight, size: 180.0, margin: 10.0}
+    }
+}
+
+impl Minimap
+{
+    // left and top of the minimap in the window and the size of one tile on it
+    pub fn layout(&self, window: (f32, f32), columns: usize, rows: usize) -> (f32, f32, f32)
+    {
+        let cell_size = self.size / columns.max(rows).max(1) as f32;
+        let (width, height) = (columns as f32 * cell_size, rows as f32 * cell_size);
+        let left = match self.corner
+        {
+            Corner::TopLeft | Corner::BottomLeft => self.margin,
+            Corner::TopRight | Corner::BottomRight => window.0 - width - self.margin
+        };
+        let top = match self.corner
+        {
+            Corner::TopLeft | Corner::TopRight => self.margin,
+            Corner::BottomLeft | Corner::BottomRight => window.1 - height - self.margin
+        };
+        (left, top, cell_size)
+    }
+}
+
+impl FromStr for Minimap
+{
+    type Err = String;
+
+    // `<corner>` or `<corner>:<size in pixels>`, e.g. `bottom-left` or `top-right:240`
+    fn from_str(value: &str) -> Result<Self, Self::Err>
+    {
+        let (corner, size) = match value.split_once(':')
+        {
+            Some((corner, size)) => (corner, Some(size)),
+            None => (value, None)
+        };
+        let mut minimap = Minimap{corner: corner.parse::<Corner>()?, ..Minimap::default()};
+        if let Some(size) = size
+        {
+            minimap.size = match size.parse::<f32>()
+            {
+                Ok(size) if size > 0.0 => size,
+                _ => return Err(format!("invalid minimap size `{}`", size))
+            };
+        }
+        Ok(minimap)
+    }
+}
+
+// the tiles to draw on the minimap, the exit and the key only show up once the player has seen them
+pub fn explored_tiles(simulation: &Simulation) -> impl Iterator<Item = ((usize, usize), Tile)> + '_
+{
+    simulation.map.iter().filter(|((x, y), _)| simulation.is_explored(*x, *y)).map(|(position, tile)| (position, *tile))
+}
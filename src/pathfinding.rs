@@ -0,0 +1,112 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use crate::grid::{Grid, Tile};
+use crate::direction::Direction;
+
+// with turn costs the same tile is worth a different amount depending on the heading,
+// so the search state is the position together with the direction it was entered from
+type Node = ((usize, usize), Option<Direction>);
+
+// cost of one step, turn costs are given in the same unit so a turn can be cheaper than a step
+pub const STEP_COST: u32 = 10;
+
+// A* over the walkable tiles from `start` to `goal`. Every change of direction costs `turn_cost`
+// on top of the step, `facing` is the direction the walker already looks in at the start.
+// The returned path leaves out `start` and ends with `goal`.
+pub fn find_path(grid: &Grid<Tile>, start: (usize, usize), facing: Option<Direction>, goal: (usize, usize), turn_cost: u32) -> Option<Vec<(usize, usize)>>
+{
+    if !grid.is_walkable(goal.0, goal.1)
+    {
+        return None;
+    }
+
+    let heuristic = |(x, y): (usize, usize)| (x.abs_diff(goal.0) + y.abs_diff(goal.1)) as u32 * STEP_COST;
+
+    let mut open: BinaryHeap<Reverse<(u32, u32, usize)>> = BinaryHeap::new();
+    let mut nodes: Vec<Node> = Vec::new();
+    let mut costs: HashMap<Node, u32> = HashMap::new();
+    let mut came_from: HashMap<Node, Node> = HashMap::new();
+
+    let start_node = (start, facing);
+    nodes.push(start_node);
+    costs.insert(start_node, 0);
+    open.push(Reverse((heuristic(start), 0, 0)));
+
+    while let Some(Reverse((_, cost, node_index))) = open.pop()
+    {
+        let node = nodes[node_index];
+        if costs.get(&node).is_some_and(|best| *best < cost)
+        {
+            continue;
+        }
+        if node.0 == goal
+        {
+            return Some(rebuild_path(&came_from, node));
+        }
+
+        let (position, heading) = node;
+        for direction in Direction::ALL
+        {
+            let next = match direction.step(position.0, position.1)
+            {
+                Some(next) if grid.is_walkable(next.0, next.1) => next,
+                _ => continue
+            };
+            let turn = match heading
+            {
+                Some(heading) if heading != direction => turn_cost,
+                _ => 0
+            };
+            let next_node = (next, Some(direction));
+            let next_cost = cost + STEP_COST + turn;
+            if costs.get(&next_node).is_none_or(|best| next_cost < *best)
+            {
+                costs.insert(next_node, next_cost);
+                came_from.insert(next_node, node);
+                nodes.push(next_node);
+                open.push(Reverse((next_cost + heuristic(next), next_cost, nodes.len() - 1)));
+            }
+        }
+    }
+    None
+}
+
+fn rebuild_path(came_from: &HashMap<Node, Node>, mut node: Node) -> Vec<(usize, usize)>
+{
+    let mut path = vec![node.0];
+    while let Some(previous) = came_from.get(&node)
+    {
+        node = *previous;
+        path.push(node.0);
+    }
+    // drop the start, the walker is already standing there
+    path.pop();
+    path.reverse();
+    path
+}
+
+// number of steps from `start` to every tile, None for walls and tiles that can't be reached
+pub fn distances(grid: &Grid<Tile>, start: (usize, usize)) -> Grid<Option<usize>>
+{
+    let mut distances = Grid::new(grid.width(), grid.height(), None);
+    if !grid.is_walkable(start.0, start.1)
+    {
+        return distances;
+    }
+
+    distances[start] = Some(0);
+    let mut queue = VecDeque::from([start]);
+    while let Some(position) = queue.pop_front()
+    {
+        let distance = distances[position].unwrap_or(0);
+        for next in grid.walkable_neighbors(position.0, position.1)
+        {
+            if distances[next].is_none()
+            {
+                distances[next] = Some(distance + 1);
+                queue.push_back(next);
+            }
+        }
+    }
+    distances
+}
@@ -1,70 +1,73 @@
-use ggez::{
-    graphics::{self, DrawParam},
-    event::KeyCode,
-    Context,
-    GameResult,
-    mint::{Point2}
-};
-use crate::assets::Assets;
+use crate::direction::Direction;
+use crate::keys::Keys;
 
 #[derive(Clone, Copy)]
 pub struct Player
 {
     pub x: usize,
     pub y: usize,
-    pub direction: KeyCode,
+    // the cell the player stepped from, the sprite slides from there while the step timer runs
+    pub previous: (usize, usize),
+    pub direction: Direction,
     pub has_key: bool,
-    pub is_on_exit: bool
+    pub keys: Keys,
+    // the player steps on a timer like the bots, however often the key repeats
+    pub time_for_step: f32,
+    pub time_until_next_step: f32,
+    // the direction the player walked in last, None once they stopped
+    pub moving: Option<Direction>,
+    // a turn pressed a moment before it's possible, taken as soon as the way opens
+    pub buffered_turn: Option<Direction>,
+    pub buffer_duration: f32,
+    pub time_until_buffer_expires: f32
 }
 
 impl Player
 {
     pub fn new(x: usize, y: usize) -> Self
     {
-        Player{x: x, y: y, direction: KeyCode::S, has_key: false, is_on_exit: false}
+        Player{x: x, y: y, previous: (x, y), direction: Direction::Down, has_key: false, keys: Keys::default(), time_for_step: 0.08, time_until_next_step: 0.0, moving: None, buffered_turn: None, buffer_duration: 0.25, time_until_buffer_expires: 0.0}
     }
 
-    pub fn update(&mut self, x: usize, y: usize, is_on_exit: bool)
+    pub fn update(&mut self, x: usize, y: usize)
     {
         self.x = x;
         self.y = y;
-        self.is_on_exit = is_on_exit;
     }
 
-    pub fn update_direction(&mut self, keycode: KeyCode)
+    pub fn update_direction(&mut self, direction: Direction)
     {
-        self.direction = keycode;
+        self.direction = direction;
     }
 
-    pub fn draw(&self, ctx: &mut Context, assets: &Assets, x_sq: i32, y_sq: i32) -> GameResult
+    pub fn buffer_turn(&mut self, direction: Direction)
     {
-        let draw_param = DrawParam::new().dest(Point2{x:x_sq as f32, y:y_sq as f32});
-        graphics::draw(ctx, &assets.floor, draw_param)?;
-        if self.has_key == false && self.is_on_exit == true
-        {
-            graphics::draw(ctx, &assets.door, draw_param)?;
-        }
+        self.buffered_turn = Some(direction);
+        self.time_until_buffer_expires = self.buffer_duration;
+    }
 
-        match self.direction 
+    // counts both timers down, returns whether the player may step now
+    pub fn tick(&mut self, dt: f32) -> bool
+    {
+        self.time_until_buffer_expires -= dt;
+        if self.time_until_buffer_expires <= 0.0
         {
-            KeyCode::D => 
-            {
-                graphics::draw(ctx, &assets.player_right, draw_param)?;
-            },
-            KeyCode::A =>
-            {
-                graphics::draw(ctx, &assets.player_left, draw_param)?;
-            },
-            KeyCode::W => 
-            {
-                graphics::draw(ctx, &assets.player_up, draw_param)?;
-            },
-            KeyCode::S => 
-            {
-                graphics::draw(ctx, &assets.player_down, draw_param)?;
-            },
-            _ => ()
+            self.buffered_turn = None;
         }
-        Ok(())
+        self.time_until_next_step -= dt;
+        self.time_until_next_step <= 0.0
     }
-}
\ No newline at end of file
+
+    // the time left over from this step counts towards the next one, so the speed doesn't depend on the frame rate
+    pub fn restart_timer(&mut self)
+    {
+        self.time_until_next_step += self.time_for_step;
+    }
+
+    // standing still doesn't save up steps for later
+    pub fn stop(&mut self)
+    {
+        self.moving = None;
+        self.time_until_next_step = self.time_until_next_step.max(0.0);
+    }
+}
@@ -0,0 +1,627 @@
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use crate::bot::{Bot, BotConfig, Behaviour};
+use crate::player::Player;
+use crate::maze_generator::Graph;
+use crate::maze_algorithms::Algorithm;
+use crate::grid::{Grid, Tile};
+use crate::direction::Direction;
+use crate::brain;
+use crate::difficulty::Difficulty;
+use crate::visibility::{self, Sight};
+use crate::keys;
+use crate::pathfinding;
+use crate::solver::Solver;
+
+// dead ends in a generated patrol route
+const PATROL_ROUTE_LENGTH: usize = 3;
+// locked doors that fail the reachability check are placed again this many times at most
+const PLACEMENT_ATTEMPTS: usize = 10;
+// seconds between two hints
+pub const HINT_COOLDOWN: f32 = 10.0;
+// seconds a hint stays on screen
+pub const HINT_DURATION: f32 = 3.0;
+// score points every hint takes off
+pub const HINT_COST: u32 = 50;
+// points for every cell of the maze, half of them for the key and half for the escape
+const POINTS_PER_CELL: f32 = 10.0;
+// seconds per cell of the maze to escape in before the time bonus runs out
+const PAR_TIME_PER_CELL: f32 = 0.5;
+// points for every second faster than the par time
+const POINTS_PER_SECOND: f32 = 10.0;
+// what the player sees of the maze without fog of war, only decides what they explore
+pub const PLAYER_SIGHT: Sight = Sight::LineOfSight(6);
+
+#[derive(PartialEq, Debug)]
+pub enum State
+{
+    Start,
+    MainState,
+    Credits(String)
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Input
+{
+    // no direction held
+    Idle,
+    // a direction was just pressed, and is held for now
+    Move(Direction),
+    // a direction pressed earlier is still held
+    Hold(Direction)
+}
+
+#[derive(Clone, Debug)]
+pub struct Settings
+{
+    pub seed: u64,
+    pub algorithm: Algorithm,
+    // size of the maze in graph cells, the tile map is twice as big plus the outer wall
+    pub width: usize,
+    pub height: usize,
+    // share of dead ends opened into loops, 0.0 is a perfect maze and 1.0 has no dead ends
+    pub loop_density: f64,
+    pub difficulty: Difficulty,
+    // behaviour and speed of each bot, repeated when the difficulty asks for more bots. Empty uses the difficulty's behaviour
+    pub bots: Vec<BotConfig>,
+    // waypoint loops in tile coordinates, handed to the patrollers and custom bots in order. One without
+    // a route of its own, or whose waypoints are all walls, gets one through the nearby dead ends.
+    pub patrol_routes: Vec<Vec<(usize, usize)>>,
+    // seconds a bot searches around the spot it lost the player before patrolling again
+    pub bot_search_duration: f32,
+    // fog of war, only the tiles in sight are shown and the rest of the explored maze is dimmed
+    pub fog: Option<Sight>,
+    // coloured doors locked on the way to the exit, at most one of each colour
+    pub locked_doors: usize,
+    // the fewest steps between the player and a bot at the start, less only in mazes too small for it
+    pub bot_spawn_distance: usize,
+    // how many hints the player can ask for in a level
+    pub hints: usize
+}
+
+impl Settings
+{
+    pub fn with_seed(seed: u64) -> Self
+    {
+        Settings{seed: seed, algorithm: Algorithm::default(), width: 10, height: 10, loop_density: 0.5, difficulty: Difficulty::default(), bots: Vec::new(), patrol_routes: Vec::new(), bot_search_duration: 5.0, fog: None, locked_doors: 0, bot_spawn_distance: 10, hints: 3}
+    }
+}
+
+// how the run went so far, only counted while the game is on
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct Stats
+{
+    // seconds since the start
+    pub elapsed: f32,
+    // tiles the player walked
+    pub steps: usize,
+    // seconds from the start until the key was picked up
+    pub key_time: Option<f32>,
+    pub escaped: bool
+}
+
+// the way to the next objective, shown for a moment
+#[derive(Clone, PartialEq, Debug)]
+pub struct Hint
+{
+    // from the player to the objective, both included
+    pub path: Vec<(usize, usize)>,
+    pub time_left: f32
+}
+
+// All of the game rules without anything that needs a window, the renderer in `game` only
+// forwards input to `step` and draws whatever state the simulation is in.
+pub struct Simulation
+{
+    pub player: Player,
+    pub bots: Vec<Bot>,
+    pub game_state: State,
+    pub map: Grid<Tile>,
+    pub exit: (usize, usize),
+    pub time_until_bot_speed_up: f32,
+    pub settings: Settings,
+    // tiles the player has stood on or seen so far
+    pub explored: Grid<bool>,
+    // tiles the player sees right now
+    pub visible: Grid<bool>,
+    pub hint: Option<Hint>,
+    pub hints_left: usize,
+    pub time_until_hint: f32,
+    // score points lost to hints
+    pub penalty: u32,
+    pub stats: Stats,
+    // every random choice of the bots comes from here, so the seed replays the whole game
+    rng: StdRng
+}
+
+impl Simulation
+{
+    // Errors when the settings leave no room for the key or no way to win, e.g. in a 1x1 maze
+    pub fn new(settings: Settings) -> Result<Self, String>
+    {
+        let mut graph = Graph::with_seed(settings.width, settings.height, settings.seed);
+        settings.algorithm.generate(&mut graph);
+        graph.braid(settings.loop_density);
+        let maze = Grid::from_chars(&graph.draw_maze());
+
+        // the placement rng is derived from the same seed, so bots and key land on the same tiles every time
+        let mut rng = StdRng::seed_from_u64(settings.seed);
+        let player = Player::new(1, 1);
+        let start = (player.x, player.y);
+
+        // everything is placed by how far it is to walk there from the start
+        let distances = pathfinding::distances(&maze, start);
+        let farthest = distances.iter().filter_map(|(_, distance)| *distance).max().unwrap_or(0);
+        let exit = farthest_dead_end(&maze, &distances);
+        let spawn_distance = settings.bot_spawn_distance.min(farthest / 2);
+
+        let preset = settings.difficulty.preset();
+        let bot_count = preset.bot_count.max(settings.bots.len());
+        let difficulty_bots = [BotConfig::new(preset.behaviour)];
+        let profiles = if settings.bots.is_empty() { &difficulty_bots[..] } else { &settings.bots[..] };
+        let mut map = maze;
+        map.set(exit.0, exit.1, Tile::Door);
+
+        let mut occupied = vec![start];
+        let mut bots = Vec::new();
+        for config in profiles.iter().cycle().take(bot_count)
+        {
+            let bot_location = match generate_location(&map, &distances, spawn_distance, &occupied, &mut rng)
+            {
+                Some(bot_location) => bot_location,
+                None => break
+            };
+            let mut bot = Bot::from_config(bot_location.0, bot_location.1, config, &preset);
+            bot.direction = Direction::ALL[rng.gen_range(0..4)];
+            bot.search_duration = settings.bot_search_duration;
+            occupied.push(bot_location);
+            bots.push(bot);
+        }
+
+        // in the far half of the maze, so it's never picked up on the first few steps
+        let key_location = generate_location(&map, &distances, farthest / 2, &occupied, &mut rng)
+            .or_else(|| generate_location(&map, &distances, 1, &occupied, &mut rng))
+            .ok_or_else(|| String::from("the maze has no room left for the key"))?;
+        map.set(key_location.0, key_location.1, Tile::Key);
+        occupied.push(key_location);
+        let map = lock_doors(&map, start, exit, settings.locked_doors, &occupied, &mut rng)?;
+
+        let mut routes = settings.patrol_routes.iter();
+        for bot in bots.iter_mut().filter(|bot| matches!(bot.behaviour, Behaviour::Patroller | Behaviour::Custom))
+        {
+            let route: Vec<(usize, usize)> = routes.next().map(|route| route.iter().copied().filter(|(x, y)| map.is_walkable(*x, *y)).collect()).unwrap_or_default();
+            bot.route = if route.is_empty() { brain::generate_route(&map, (bot.x, bot.y), PATROL_ROUTE_LENGTH) } else { route };
+        }
+        // guards watch the key until the player has it, custom bots get the same post
+        for bot in bots.iter_mut().filter(|bot| matches!(bot.behaviour, Behaviour::Guard{..} | Behaviour::Custom))
+        {
+            bot.post = Some(key_location);
+        }
+
+        let explored = Grid::new(map.width(), map.height(), false);
+        let visible = Grid::new(map.width(), map.height(), false);
+        let hints_left = settings.hints;
+        let mut simulation = Simulation
+        {
+            player: player,
+            bots: bots,
+            game_state: State::Start,
+            map: map,
+            exit: exit,
+            time_until_bot_speed_up: 1.0,
+            settings: settings,
+            explored: explored,
+            visible: visible,
+            hint: None,
+            hints_left: hints_left,
+            time_until_hint: 0.0,
+            penalty: 0,
+            stats: Stats::default(),
+            rng: rng
+        };
+        simulation.reveal();
+        Ok(simulation)
+    }
+
+    // only before the game has started, builds the level again for the new difficulty
+    pub fn select_difficulty(&mut self, difficulty: Difficulty)
+    {
+        if self.game_state == State::Start && self.settings.difficulty != difficulty
+        {
+            let mut settings = self.settings.clone();
+            settings.difficulty = difficulty;
+            // a difficulty whose bots leave no room for the key keeps the current level
+            if let Ok(simulation) = Simulation::new(settings)
+            {
+                *self = simulation;
+            }
+        }
+    }
+
+    pub fn start(&mut self)
+    {
+        if self.game_state == State::Start
+        {
+            self.game_state = State::MainState;
+        }
+    }
+
+    // Advances the game by `dt` seconds. The player and every bot step on their own timers and within
+    // a tick the player always moves first, then the bots in order, so the same inputs give the same game.
+    pub fn step(&mut self, input: Input, dt: f32)
+    {
+        if self.game_state != State::MainState
+        {
+            return;
+        }
+
+        self.stats.elapsed += dt;
+        self.step_hint(dt);
+        self.step_player(input, dt);
+        if self.game_state != State::MainState
+        {
+            return;
+        }
+
+        let player = (self.player.x, self.player.y);
+        self.time_until_bot_speed_up -= dt;
+        for index in 0..self.bots.len()
+        {
+            self.step_bot(index, player, dt);
+            if self.game_state != State::MainState
+            {
+                return;
+            }
+        }
+
+        if self.time_until_bot_speed_up <= 0.0
+        {
+            for bot in &mut self.bots
+            {
+                bot.speed_up();
+            }
+            self.restart_timer();
+        }
+    }
+
+    // Half of the points of the maze for the key and the other half, with a bonus for every second faster than
+    // the par time, for the escape. Bigger mazes are worth more points and harder difficulties multiply them.
+    // The hints are taken off at the end, the score never goes below zero.
+    pub fn score(&self) -> u32
+    {
+        let cells = (self.settings.width * self.settings.height) as f32;
+        let mut points = 0.0;
+        if self.player.has_key
+        {
+            points += cells * POINTS_PER_CELL / 2.0;
+        }
+        if self.stats.escaped
+        {
+            let time_bonus = (cells * PAR_TIME_PER_CELL - self.stats.elapsed).max(0.0) * POINTS_PER_SECOND;
+            points += cells * POINTS_PER_CELL / 2.0 + time_bonus;
+        }
+        let points = (points * self.settings.difficulty.preset().score_multiplier).round() as u32;
+        points.saturating_sub(self.penalty)
+    }
+
+    fn step_hint(&mut self, dt: f32)
+    {
+        self.time_until_hint = (self.time_until_hint - dt).max(0.0);
+        if let Some(hint) = &mut self.hint
+        {
+            hint.time_left -= dt;
+            if hint.time_left <= 0.0
+            {
+                self.hint = None;
+            }
+        }
+    }
+
+    // Shows the shortest way to the key, or to the exit once the player has it. When a locked door is
+    // in the way, the way to the nearest coloured key the player can get to is shown instead.
+    // Returns false, and costs nothing, while on cooldown, without hints left or without any way to show.
+    pub fn request_hint(&mut self) -> bool
+    {
+        if self.game_state != State::MainState || self.hints_left == 0 || self.time_until_hint > 0.0
+        {
+            return false;
+        }
+        let path = match self.path_to_objective()
+        {
+            Some(path) => path,
+            None => return false
+        };
+        self.hint = Some(Hint{path: path, time_left: HINT_DURATION});
+        self.hints_left -= 1;
+        self.time_until_hint = HINT_COOLDOWN;
+        self.penalty += HINT_COST;
+        true
+    }
+
+    fn path_to_objective(&self) -> Option<Vec<(usize, usize)>>
+    {
+        let player = (self.player.x, self.player.y);
+        // the doors the player has the key for are as good as open
+        let mut map = self.map.clone();
+        for position in self.map.positions().filter(|(x, y)| !self.map.is_walkable(*x, *y) && self.can_enter(*x, *y))
+        {
+            map[position] = Tile::Floor;
+        }
+
+        let objective = if self.player.has_key { Some(self.exit) } else { map.positions().find(|position| map[*position] == Tile::Key) };
+        let path = objective.and_then(|objective| Solver::BreadthFirst.solve(&map, player, objective).path);
+        if path.is_some()
+        {
+            return path;
+        }
+        let distances = pathfinding::distances(&map, player);
+        let nearest_key = map.positions()
+            .filter(|position| matches!(map[*position], Tile::ColouredKey(_)))
+            .filter_map(|position| distances[position].map(|distance| (distance, position)))
+            .min()?;
+        Solver::BreadthFirst.solve(&map, player, nearest_key.1).path
+    }
+
+    // A held direction keeps the player walking, blocked by a wall they go on in the direction they were
+    // already walking. A turn pressed a moment too early is buffered and taken at the next opening.
+    fn step_player(&mut self, input: Input, dt: f32)
+    {
+        let is_ready = self.player.tick(dt);
+        let held = match input
+        {
+            Input::Idle => None,
+            Input::Move(direction) =>
+            {
+                self.player.buffer_turn(direction);
+                Some(direction)
+            }
+            Input::Hold(direction) => Some(direction)
+        };
+        if !is_ready
+        {
+            return;
+        }
+
+        let (x, y) = (self.player.x, self.player.y);
+        self.player.previous = (x, y);
+        let keep_going = if held.is_some() { self.player.moving } else { None };
+        let next = [self.player.buffered_turn, held, keep_going].into_iter().flatten().find(|direction| direction.step(x, y).is_some_and(|(x, y)| self.can_enter(x, y)));
+        match next
+        {
+            Some(direction) =>
+            {
+                if self.player.buffered_turn == Some(direction)
+                {
+                    self.player.buffered_turn = None;
+                }
+                self.player.moving = Some(direction);
+                self.player.restart_timer();
+                self.update_player_position(direction);
+            }
+            None =>
+            {
+                if let Some(direction) = held
+                {
+                    self.player.update_direction(direction);
+                }
+                self.player.stop();
+            }
+        }
+    }
+
+    fn step_bot(&mut self, index: usize, player: (usize, usize), dt: f32)
+    {
+        let bot = &mut self.bots[index];
+        let knows_player = bot.perceive(&self.map, player, dt);
+        bot.time_until_next_step -= dt;
+        if bot.time_until_next_step <= 0.0
+        {
+            let elapsed = bot.time_for_step - bot.time_until_next_step;
+            bot.previous = (bot.x, bot.y);
+            let player = if knows_player { Some((player, self.player.direction)) } else { None };
+            let next = bot.think(&self.map, player, elapsed, &mut self.rng).and_then(|direction| direction.step(bot.x, bot.y));
+            if let Some((new_x, new_y)) = next
+            {
+                self.update_bot_position(index, new_x, new_y);
+            }
+            self.bots[index].restart_timer();
+        }
+    }
+
+    pub fn update_player_position(&mut self, direction: Direction)
+    {
+        self.player.update_direction(direction);
+        let (new_x, new_y) = match direction.step(self.player.x, self.player.y)
+        {
+            Some(position) => position,
+            None => return
+        };
+        match self.map.get(new_x, new_y).copied()
+        {
+            None | Some(Tile::Wall) => return,
+            Some(Tile::LockedDoor(colour)) if !self.player.keys.contains(colour) => return,
+            _ if self.is_bot_at(new_x, new_y) =>
+            {
+                self.game_state = State::Credits(String::from("Game Over!"));
+                return;
+            }
+            Some(Tile::Door) if self.player.has_key =>
+            {
+                self.stats.steps += 1;
+                self.stats.escaped = true;
+                self.game_state = State::Credits(String::from("You found the exit!"));
+                return;
+            }
+            Some(Tile::Key) =>
+            {
+                self.player.has_key = true;
+                self.stats.key_time = Some(self.stats.elapsed);
+                self.map.set(new_x, new_y, Tile::Floor);
+                // nothing left to guard here, the guards fall back to the exit
+                let exit = self.exit;
+                for bot in self.bots.iter_mut().filter(|bot| bot.post.is_some())
+                {
+                    bot.post = Some(exit);
+                }
+            }
+            Some(Tile::ColouredKey(colour)) =>
+            {
+                self.player.keys.insert(colour);
+                self.map.set(new_x, new_y, Tile::Floor);
+            }
+            // the door stays open for good, the bots can walk through it from now on as well
+            Some(Tile::LockedDoor(_)) =>
+            {
+                self.map.set(new_x, new_y, Tile::Floor);
+            }
+            _ => ()
+        }
+        self.player.update(new_x, new_y);
+        self.stats.steps += 1;
+        self.reveal();
+    }
+
+    // updates what the player sees and marks all of it as explored
+    pub fn reveal(&mut self)
+    {
+        let sight = self.settings.fog.unwrap_or(PLAYER_SIGHT);
+        self.visible = visibility::visible_tiles(&self.map, (self.player.x, self.player.y), sight);
+        for ((x, y), _) in self.visible.iter().filter(|(_, visible)| **visible)
+        {
+            self.explored.set(x, y, true);
+        }
+    }
+
+    pub fn is_explored(&self, x: usize, y: usize) -> bool
+    {
+        self.explored.get(x, y).is_some_and(|explored| *explored)
+    }
+
+    // without fog of war the whole maze is in view
+    pub fn is_visible(&self, x: usize, y: usize) -> bool
+    {
+        self.settings.fog.is_none() || self.visible.get(x, y).is_some_and(|visible| *visible)
+    }
+
+    // the part of the hint that is drawn, under fog of war it stops at the unexplored tiles
+    pub fn hint_tiles(&self) -> impl Iterator<Item = (usize, usize)> + '_
+    {
+        let path = self.hint.as_ref().map(|hint| &hint.path[..]).unwrap_or_default();
+        path.iter().copied().filter(move |(x, y)| self.settings.fog.is_none() || self.is_explored(*x, *y))
+    }
+
+    // the bots the player can see, the only ones drawn or shown in the debug overlay
+    pub fn visible_bots(&self) -> impl Iterator<Item = &Bot> + '_
+    {
+        self.bots.iter().filter(move |bot| self.is_visible(bot.x, bot.y))
+    }
+
+    // whether the player can pick up the key and reach the exit from where they stand
+    pub fn is_solvable(&self) -> bool
+    {
+        is_solvable(&self.map, (self.player.x, self.player.y), self.exit)
+    }
+
+    // the player also walks through the locked doors they have the key for
+    pub fn can_enter(&self, x: usize, y: usize) -> bool
+    {
+        match self.map.get(x, y)
+        {
+            Some(Tile::LockedDoor(colour)) => self.player.keys.contains(*colour),
+            _ => self.map.is_walkable(x, y)
+        }
+    }
+
+    pub fn update_bot_position(&mut self, index: usize, new_x: usize, new_y: usize)
+    {
+        if !self.map.is_walkable(new_x, new_y)
+        {
+            return;
+        }
+        else if (new_x, new_y) == (self.player.x, self.player.y)
+        {
+            self.game_state = State::Credits(String::from("Game Over!"));
+            return;
+        }
+        else if self.is_bot_at(new_x, new_y)
+        {
+            // bots never share a tile, this one waits and its brain finds another way if it has to
+            return;
+        }
+        self.bots[index].update_position(new_x, new_y);
+    }
+
+    pub fn is_bot_at(&self, x: usize, y: usize) -> bool
+    {
+        self.bots.iter().any(|bot| (bot.x, bot.y) == (x, y))
+    }
+
+    pub fn restart_timer(&mut self)
+    {
+        self.time_until_bot_speed_up = 1.0;
+    }
+}
+
+// a random floor tile at least `min_distance` steps from the start that none of the `occupied` positions is standing on
+fn generate_location(map: &Grid<Tile>, distances: &Grid<Option<usize>>, min_distance: usize, occupied: &[(usize, usize)], rng: &mut StdRng) -> Option<(usize, usize)>
+{
+    let candidates: Vec<(usize, usize)> = map.positions()
+        .filter(|position| map[*position] == Tile::Floor && !occupied.contains(position))
+        .filter(|position| distances.get(position.0, position.1).copied().flatten().is_some_and(|distance| distance >= min_distance))
+        .collect();
+    if candidates.is_empty()
+    {
+        return None;
+    }
+    Some(candidates[rng.gen_range(0..candidates.len())])
+}
+
+// the dead end the longest walk away from the start, or the farthest tile of a maze without dead ends
+fn farthest_dead_end(map: &Grid<Tile>, distances: &Grid<Option<usize>>) -> (usize, usize)
+{
+    let distance = |position: &(usize, usize)| distances[*position].unwrap_or(0);
+    map.dead_ends().max_by_key(distance)
+        .or_else(|| map.positions().filter(|(x, y)| map.is_walkable(*x, *y)).max_by_key(distance))
+        .unwrap_or((map.width() - 2, map.height() - 2))
+}
+
+// Locks up to `count` doors in a copy of `map`. Doors that leave the level unsolvable are placed again with
+// the next numbers of `rng`, and after `PLACEMENT_ATTEMPTS` tries the level goes without locked doors.
+// Errors when even then the key or the exit can't be reached from `start`.
+pub fn lock_doors(map: &Grid<Tile>, start: (usize, usize), exit: (usize, usize), count: usize, occupied: &[(usize, usize)], rng: &mut StdRng) -> Result<Grid<Tile>, String>
+{
+    for _ in 0..PLACEMENT_ATTEMPTS
+    {
+        let mut locked = map.clone();
+        keys::place_locked_doors(&mut locked, start, exit, count, occupied, rng);
+        if is_solvable(&locked, start, exit)
+        {
+            return Ok(locked);
+        }
+    }
+    if is_solvable(map, start, exit)
+    {
+        Ok(map.clone())
+    }
+    else
+    {
+        Err(String::from("the key or the exit can't be reached from the start"))
+    }
+}
+
+// every coloured door can be opened, and then the key and the exit can be walked to from `start`
+fn is_solvable(map: &Grid<Tile>, start: (usize, usize), exit: (usize, usize)) -> bool
+{
+    if !keys::can_collect_all(map, start)
+    {
+        return false;
+    }
+    let mut open = map.clone();
+    for position in map.positions().filter(|position| matches!(map[*position], Tile::LockedDoor(_)))
+    {
+        open[position] = Tile::Floor;
+    }
+    let distances = pathfinding::distances(&open, start);
+    let keys_reachable = open.positions().filter(|position| open[*position] == Tile::Key).all(|position| distances[position].is_some());
+    keys_reachable && distances.get(exit.0, exit.1).copied().flatten().is_some()
+}
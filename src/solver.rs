@@ -0,0 +1,338 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::str::FromStr;
+use crate::grid::{Grid, Tile};
+use crate::direction::Direction;
+use crate::pathfinding::STEP_COST;
+
+// the two tiles at the ends of a step, the smaller one first so both ways give the same passage
+type Passage = ((usize, usize), (usize, usize));
+
+// What a solver found out about the way from the start to the goal.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Solution
+{
+    // from the start to the goal, both included, None when the solver gave up
+    pub path: Option<Vec<(usize, usize)>>,
+    // number of distinct tiles the solver looked at
+    pub visited: usize,
+    // every tile in the order the solver got to it, the searches list each tile once
+    // while the walkers list every step they take, dead ends and all
+    pub trace: Vec<(usize, usize)>
+}
+
+impl Solution
+{
+    pub fn is_solved(&self) -> bool
+    {
+        self.path.is_some()
+    }
+
+    // number of steps along the path
+    pub fn length(&self) -> Option<usize>
+    {
+        self.path.as_ref().map(|path| path.len() - 1)
+    }
+}
+
+// Every solver walks the walkable tiles of the grid, the same ones the player can walk on.
+pub trait MazeSolver
+{
+    fn solve(&self, grid: &Grid<Tile>, start: (usize, usize), goal: (usize, usize)) -> Solution;
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum Solver
+{
+    #[default]
+    BreadthFirst,
+    Dijkstra,
+    AStar,
+    WallFollower,
+    Tremaux
+}
+
+impl Solver
+{
+    pub const ALL: [Solver; 5] = [Solver::BreadthFirst, Solver::Dijkstra, Solver::AStar, Solver::WallFollower, Solver::Tremaux];
+
+    pub fn solver(&self) -> Box<dyn MazeSolver>
+    {
+        match self
+        {
+            Solver::BreadthFirst => Box::new(BreadthFirst),
+            Solver::Dijkstra => Box::new(Dijkstra),
+            Solver::AStar => Box::new(AStar),
+            Solver::WallFollower => Box::new(WallFollower{right_hand: true}),
+            Solver::Tremaux => Box::new(Tremaux)
+        }
+    }
+
+    pub fn solve(&self, grid: &Grid<Tile>, start: (usize, usize), goal: (usize, usize)) -> Solution
+    {
+        self.solver().solve(grid, start, goal)
+    }
+
+    // whether the path is always a shortest one
+    pub fn is_optimal(&self) -> bool
+    {
+        matches!(self, Solver::BreadthFirst | Solver::Dijkstra | Solver::AStar)
+    }
+}
+
+impl FromStr for Solver
+{
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err>
+    {
+        match name.to_lowercase().replace(['-', '_'], "").as_str()
+        {
+            "bfs" | "breadthfirst" => Ok(Solver::BreadthFirst),
+            "dijkstra" => Ok(Solver::Dijkstra),
+            "astar" | "a*" => Ok(Solver::AStar),
+            "wallfollower" => Ok(Solver::WallFollower),
+            "tremaux" | "trémaux" => Ok(Solver::Tremaux),
+            _ => Err(format!("unknown solver `{}`", name))
+        }
+    }
+}
+
+// the walkable tiles a step away from `position`, in the order of `Direction::ALL`
+fn open_neighbors(grid: &Grid<Tile>, position: (usize, usize)) -> impl Iterator<Item = (usize, usize)> + '_
+{
+    Direction::ALL.into_iter().filter_map(move |direction| direction.step(position.0, position.1)).filter(|(x, y)| grid.is_walkable(*x, *y))
+}
+
+fn is_open(grid: &Grid<Tile>, start: (usize, usize), goal: (usize, usize)) -> bool
+{
+    grid.is_walkable(start.0, start.1) && grid.is_walkable(goal.0, goal.1)
+}
+
+fn rebuild_path(came_from: &HashMap<(usize, usize), (usize, usize)>, goal: (usize, usize)) -> Vec<(usize, usize)>
+{
+    let mut path = vec![goal];
+    let mut current = goal;
+    while let Some(previous) = came_from.get(&current)
+    {
+        path.push(*previous);
+        current = *previous;
+    }
+    path.reverse();
+    path
+}
+
+// drops every detour from a walk, what is left is a path without a tile twice
+fn erase_loops(walk: &[(usize, usize)]) -> Vec<(usize, usize)>
+{
+    let mut path: Vec<(usize, usize)> = Vec::new();
+    for position in walk
+    {
+        match path.iter().position(|visited| visited == position)
+        {
+            Some(index) => path.truncate(index + 1),
+            None => path.push(*position)
+        }
+    }
+    path
+}
+
+fn count_distinct(trace: &[(usize, usize)]) -> usize
+{
+    let mut seen = trace.to_vec();
+    seen.sort();
+    seen.dedup();
+    seen.len()
+}
+
+pub struct BreadthFirst;
+
+impl MazeSolver for BreadthFirst
+{
+    fn solve(&self, grid: &Grid<Tile>, start: (usize, usize), goal: (usize, usize)) -> Solution
+    {
+        if !is_open(grid, start, goal)
+        {
+            return Solution::default();
+        }
+
+        let mut came_from = HashMap::new();
+        let mut seen = Grid::new(grid.width(), grid.height(), false);
+        let mut trace = Vec::new();
+        let mut queue = VecDeque::from([start]);
+        seen[start] = true;
+        while let Some(position) = queue.pop_front()
+        {
+            trace.push(position);
+            if position == goal
+            {
+                return Solution{path: Some(rebuild_path(&came_from, goal)), visited: trace.len(), trace: trace};
+            }
+            for next in open_neighbors(grid, position)
+            {
+                if !seen[next]
+                {
+                    seen[next] = true;
+                    came_from.insert(next, position);
+                    queue.push_back(next);
+                }
+            }
+        }
+        Solution{path: None, visited: trace.len(), trace: trace}
+    }
+}
+
+// Dijkstra and A* only differ in the estimate of the rest of the way, which is zero for Dijkstra.
+fn best_first(grid: &Grid<Tile>, start: (usize, usize), goal: (usize, usize), heuristic: impl Fn((usize, usize)) -> u32) -> Solution
+{
+    if !is_open(grid, start, goal)
+    {
+        return Solution::default();
+    }
+
+    let mut costs: HashMap<(usize, usize), u32> = HashMap::from([(start, 0)]);
+    let mut came_from = HashMap::new();
+    let mut done = Grid::new(grid.width(), grid.height(), false);
+    let mut trace = Vec::new();
+    let mut open = BinaryHeap::from([Reverse((heuristic(start), 0, start))]);
+    while let Some(Reverse((_, cost, position))) = open.pop()
+    {
+        if done[position]
+        {
+            continue;
+        }
+        done[position] = true;
+        trace.push(position);
+        if position == goal
+        {
+            return Solution{path: Some(rebuild_path(&came_from, goal)), visited: trace.len(), trace: trace};
+        }
+        for next in open_neighbors(grid, position)
+        {
+            let next_cost = cost + STEP_COST;
+            if !done[next] && costs.get(&next).is_none_or(|best| next_cost < *best)
+            {
+                costs.insert(next, next_cost);
+                came_from.insert(next, position);
+                open.push(Reverse((next_cost + heuristic(next), next_cost, next)));
+            }
+        }
+    }
+    Solution{path: None, visited: trace.len(), trace: trace}
+}
+
+pub struct Dijkstra;
+
+impl MazeSolver for Dijkstra
+{
+    fn solve(&self, grid: &Grid<Tile>, start: (usize, usize), goal: (usize, usize)) -> Solution
+    {
+        best_first(grid, start, goal, |_| 0)
+    }
+}
+
+pub struct AStar;
+
+impl MazeSolver for AStar
+{
+    fn solve(&self, grid: &Grid<Tile>, start: (usize, usize), goal: (usize, usize)) -> Solution
+    {
+        best_first(grid, start, goal, |(x, y)| (x.abs_diff(goal.0) + y.abs_diff(goal.1)) as u32 * STEP_COST)
+    }
+}
+
+// Keeps one hand on the wall. Always gets out of a maze without loops, but can go round in
+// circles forever around a loop, so it gives up once it is back where it was facing the same way.
+pub struct WallFollower
+{
+    pub right_hand: bool
+}
+
+impl MazeSolver for WallFollower
+{
+    fn solve(&self, grid: &Grid<Tile>, start: (usize, usize), goal: (usize, usize)) -> Solution
+    {
+        if !is_open(grid, start, goal)
+        {
+            return Solution::default();
+        }
+
+        let mut position = start;
+        let mut facing = Direction::ALL.into_iter().find(|direction| direction.step(start.0, start.1).is_some_and(|(x, y)| grid.is_walkable(x, y))).unwrap_or(Direction::Up);
+        let mut states = HashSet::new();
+        let mut trace = vec![start];
+        while position != goal
+        {
+            if !states.insert((position, facing))
+            {
+                return Solution{path: None, visited: count_distinct(&trace), trace: trace};
+            }
+
+            let (hand, other_hand) = if self.right_hand { (facing.turn_right(), facing.turn_left()) } else { (facing.turn_left(), facing.turn_right()) };
+            let next = [hand, facing, other_hand, facing.opposite()].into_iter()
+                .find_map(|direction| direction.step(position.0, position.1).filter(|(x, y)| grid.is_walkable(*x, *y)).map(|next| (direction, next)));
+            match next
+            {
+                Some((direction, next)) =>
+                {
+                    facing = direction;
+                    position = next;
+                    trace.push(position);
+                }
+                // walled in on all sides
+                None => return Solution{path: None, visited: 1, trace: trace}
+            }
+        }
+        Solution{path: Some(erase_loops(&trace)), visited: count_distinct(&trace), trace: trace}
+    }
+}
+
+// Marks every passage it walks through and never takes one twice in the same direction, so it
+// finds the goal of any maze, loops or not, walking every passage at most twice.
+pub struct Tremaux;
+
+impl MazeSolver for Tremaux
+{
+    fn solve(&self, grid: &Grid<Tile>, start: (usize, usize), goal: (usize, usize)) -> Solution
+    {
+        if !is_open(grid, start, goal)
+        {
+            return Solution::default();
+        }
+
+        let passage = |a: (usize, usize), b: (usize, usize)| -> Passage { if a < b { (a, b) } else { (b, a) } };
+        let mut marks: HashMap<Passage, u8> = HashMap::new();
+        let mut position = start;
+        let mut came_from: Option<(usize, usize)> = None;
+        let mut trace = vec![start];
+        while position != goal
+        {
+            let mark = |a, b| marks.get(&passage(a, b)).copied().unwrap_or(0);
+            let entered_before = came_from.is_some_and(|previous| mark(previous, position) == 1 && open_neighbors(grid, position).filter(|next| *next != previous).any(|next| mark(position, next) > 0));
+            let next = match came_from
+            {
+                // back at a junction through a new passage, go back the way it came
+                Some(previous) if entered_before => Some(previous),
+                _ => open_neighbors(grid, position)
+                    .filter(|next| Some(*next) != came_from && mark(position, *next) < 2)
+                    .min_by_key(|next| mark(position, *next))
+                    .filter(|next| mark(position, *next) == 0)
+                    .or_else(|| came_from.filter(|previous| mark(*previous, position) < 2))
+                    .or_else(|| open_neighbors(grid, position).filter(|next| mark(position, *next) < 2).min_by_key(|next| mark(position, *next)))
+            };
+            let next = match next
+            {
+                Some(next) => next,
+                // every passage walked both ways, the goal can't be reached
+                None => break
+            };
+            *marks.entry(passage(position, next)).or_insert(0) += 1;
+            came_from = Some(position);
+            position = next;
+            trace.push(position);
+        }
+
+        let path = if position == goal { Some(erase_loops(&trace)) } else { None };
+        Solution{path: path, visited: count_distinct(&trace), trace: trace}
+    }
+}
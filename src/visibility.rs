@@ -0,0 +1,77 @@
+use std::str::FromStr;
+use crate::grid::{Grid, Tile};
+
+// How far the player sees, used for what they explore and, with fog of war, for what is drawn.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Sight
+{
+    // every tile up to this many tiles away, walls or not
+    Radius(usize),
+    // tiles up to this many tiles away that no wall hides
+    LineOfSight(usize)
+}
+
+impl Sight
+{
+    pub fn range(&self) -> usize
+    {
+        match *self
+        {
+            Sight::Radius(range) | Sight::LineOfSight(range) => range
+        }
+    }
+}
+
+impl FromStr for Sight
+{
+    type Err = String;
+
+    // `radius` or `sight`, optionally followed by `:<tiles>`, e.g. `radius:3` or `sight`
+    fn from_str(value: &str) -> Result<Self, Self::Err>
+    {
+        let (name, range) = match value.split_once(':')
+        {
+            Some((name, range)) => (name, Some(range)),
+            None => (value, None)
+        };
+        let range = match range.map(|range| range.parse::<usize>())
+        {
+            None => None,
+            Some(Ok(range)) if range > 0 => Some(range),
+            Some(_) => return Err(format!("invalid sight range `{}`", range.unwrap_or_default()))
+        };
+        match name.to_lowercase().as_str()
+        {
+            "radius" => Ok(Sight::Radius(range.unwrap_or(4))),
+            "sight" | "line-of-sight" => Ok(Sight::LineOfSight(range.unwrap_or(8))),
+            _ => Err(format!("unknown sight `{}`", name))
+        }
+    }
+}
+
+// the tiles seen from `from`, true for every one of them
+pub fn visible_tiles(grid: &Grid<Tile>, from: (usize, usize), sight: Sight) -> Grid<bool>
+{
+    let mut visible = Grid::new(grid.width(), grid.height(), false);
+    let range = sight.range();
+    for y in from.1.saturating_sub(range)..=from.1 + range
+    {
+        for x in from.0.saturating_sub(range)..=from.0 + range
+        {
+            let (dx, dy) = (x.abs_diff(from.0), y.abs_diff(from.1));
+            if !grid.in_bounds(x, y) || dx*dx + dy*dy > range*range
+            {
+                continue;
+            }
+            if let Sight::LineOfSight(_) = sight
+            {
+                if !grid.has_line_of_sight(from, (x, y))
+                {
+                    continue;
+                }
+            }
+            visible.set(x, y, true);
+        }
+    }
+    visible
+}
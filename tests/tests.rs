@@ -1,200 +1,348 @@
-use Maze::bot::Bot;
-use Maze::player::Player;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use Maze::bot::{Bot, BotConfig, Behaviour, BrainFactory, Vision, BotState};
+use Maze::brain::{self, BotBrain, Hunter, Ambusher, Wanderer, RandomWalker, Patroller};
+use Maze::pathfinding;
 use Maze::maze_generator::Graph;
-use Maze::game::State;
-use std::{thread, time};
-use rand::{self, Rng};
-use ggez::{
-    event, GameResult,
-    mint::{Point2}
-};
+use Maze::maze_algorithms::Algorithm;
+use Maze::simulation::{self, Simulation, State, Settings, Input};
+use Maze::grid::{Grid, Tile};
+use Maze::direction::Direction;
+use Maze::game;
+use Maze::animation;
+use Maze::assets;
+use Maze::camera::Camera;
+use Maze::minimap::{self, Minimap, Corner};
+use Maze::visibility::{self, Sight};
+use Maze::keys::{self, Colour};
+use Maze::solver::{Solver, Solution};
+use Maze::difficulty::{Difficulty, Acceleration};
 
 const WALL: char = 'W';
 const FLOOR: char = '.';
-const PLAYER: char = 'P';
-const BOT: char = 'E';
-const EXIT: char = 'V';
-const KEY: char = 'K';
 
+fn new_simulation(seed: u64) -> Simulation
+{
+    let mut simulation = Simulation::new(Settings::with_seed(seed)).unwrap();
+    simulation.start();
+    simulation
+}
 
-pub struct MazeGameMock
+// moves the bot out of the way so a test is not decided by where it happened to spawn
+fn place_bot(simulation: &mut Simulation, x: usize, y: usize)
 {
-    pub player: Player,
-    pub ai: Bot,
-    pub game_state: State,
-    pub map: Vec<Vec<char>>,
-    pub time_until_bot_speed_up: f32,
+    simulation.bots[0].update_position(x, y);
 }
 
-impl MazeGameMock {
-    pub fn new() -> GameResult<Self>
+// turns the bot towards the player the way the random walker does, if the bot can see them
+fn look_for_player(bot: &mut Bot, grid: &Grid<Tile>, player: (usize, usize))
+{
+    let player_view = if bot.can_see(grid, player) { Some((player, Direction::Down)) } else { None };
+    if let Some(direction) = brain::look_for_player(&bot.view(grid, player_view, 0.0))
     {
-        let mut graph = Graph::new(10, 10);
-        graph.generate_maze(0, 0);
-        let mut map =graph.draw_maze();
-
-        let player = Player::new(1, 1);
-
-        let bot_location = generate_location(&map);
-        let ai = Bot::new(bot_location.x, bot_location.y);
-
-        map[player.y][player.x] = PLAYER;
+        bot.direction = direction;
+    }
+}
 
-        map[ai.y][ai.x] = BOT;
-        let exit_y = map.len() - 2;
-        let exit_x = map[0].len() - 2;
+fn grid_from_rows(rows: &[&str]) -> Grid<Tile>
+{
+    let map: Vec<Vec<char>> = rows.iter().map(|row| row.chars().collect()).collect();
+    Grid::from_chars(&map)
+}
 
-        map[exit_y][exit_x] = EXIT;
+fn count_turns(start: (usize, usize), path: &[(usize, usize)]) -> usize
+{
+    let mut previous = start;
+    let mut directions: Vec<Direction> = Vec::new();
+    for next in path
+    {
+        directions.push(Direction::between(previous, *next).unwrap());
+        previous = *next;
+    }
+    directions.windows(2).filter(|pair| pair[0] != pair[1]).count()
+}
 
-        let key_location = generate_location(&map);
-        map[key_location.y][key_location.x] = KEY;
-        
-        let maze = MazeGameMock
+// a perfect maze has consistent walls on both sides of every passage and every cell reachable from (0, 0)
+fn is_perfect_maze(graph: &Graph) -> bool
+{
+    for cell in graph.cells.iter()
+    {
+        if cell.x < graph.width - 1 && cell.right != graph.cells[graph.index(cell.x+1, cell.y)].left
         {
-            player: player,
-            ai: ai,
-            game_state: State::Start,
-            map: map,
-            time_until_bot_speed_up: 1.0,
-        };
-        Ok(maze)
+            return false;
+        }
+        if cell.y < graph.height - 1 && cell.down != graph.cells[graph.index(cell.x, cell.y+1)].up
+        {
+            return false;
+        }
     }
 
-    pub fn update_player_position(&mut self, new_x: usize, new_y: usize, keycode: event::KeyCode)
+    let mut reached = vec![false; graph.width*graph.height];
+    let mut stack = vec![(0, 0)];
+    reached[0] = true;
+    let mut passages = 0;
+    while let Some((x, y)) = stack.pop()
     {
-        let mut is_on_exit = false;
-        self.player.update_direction(keycode);
-        if self.map[new_y][new_x] == WALL 
-        {
-            return;
-        } 
-        else if self.map[new_y][new_x] == BOT 
+        let cell = &graph.cells[graph.index(x, y)];
+        let mut open: Vec<(usize, usize)> = Vec::new();
+        if !cell.right
         {
-            self.game_state = State::Credits(String::from("Game Over!"));
-            let ten_millis = time::Duration::from_secs_f32(0.5);
-            thread::sleep(ten_millis);
-            return;
-        } 
-        else if self.map[new_y][new_x] == EXIT && self.player.has_key == true
+            open.push((x+1, y));
+        }
+        if !cell.down
         {
-            self.game_state = State::Credits(String::from("You found the exit!"));
-            return;
+            open.push((x, y+1));
         }
-        else if self.map[new_y][new_x] == EXIT && self.player.has_key == false
+        if !cell.left
         {
-            is_on_exit = true;
+            open.push((x-1, y));
         }
-        else if self.map[new_y][new_x] == KEY
+        if !cell.up
         {
-            self.player.has_key = true;
+            open.push((x, y-1));
         }
-        
-        match self.player.is_on_exit
+        for (next_x, next_y) in open
         {
-            true => {self.map[self.player.y][self.player.x] = EXIT;},
-            false => {self.map[self.player.y][self.player.x] = FLOOR;}
+            passages += 1;
+            let index = graph.index(next_x, next_y);
+            if !reached[index]
+            {
+                reached[index] = true;
+                stack.push((next_x, next_y));
+            }
         }
-        self.player.update(new_x, new_y, is_on_exit);
-        self.map[self.player.y][self.player.x] = PLAYER;
+    }
+    // every passage is seen from both of its cells
+    reached.iter().all(|reached| *reached) && passages / 2 == graph.width*graph.height - 1
+}
+
+fn assert_map_matches_graph(graph: &Graph)
+{
+    let map = graph.draw_maze();
+    assert_eq!(map.len(), 2*graph.height + 1);
+    assert!(map.iter().all(|row| row.len() == 2*graph.width + 1));
+    assert!(map[0].iter().all(|tile| *tile == WALL));
+    assert!(map[map.len()-1].iter().all(|tile| *tile == WALL));
+    assert!(map.iter().all(|row| row[0] == WALL && row[row.len()-1] == WALL));
+    for cell in graph.cells.iter()
+    {
+        assert_eq!(map[2*cell.y + 1][2*cell.x + 1], FLOOR);
+        assert_eq!(map[2*cell.y + 1][2*cell.x + 2] == FLOOR, !cell.right, "cell ({}, {})", cell.x, cell.y);
+        assert_eq!(map[2*cell.y + 2][2*cell.x + 1] == FLOOR, !cell.down, "cell ({}, {})", cell.x, cell.y);
+        assert_eq!(map[2*cell.y + 2][2*cell.x + 2], WALL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_speed_up() 
+    {
+        let mut bot = Bot::new(1,1);
+        let old_speed = bot.time_for_step;
+        bot.speed_up();
+        assert!(old_speed>bot.time_for_step)
+    }
+
+    #[test]
+    fn test_same_seed_same_maze()
+    {
+        let mut first = Graph::with_seed(10, 10, 42);
+        first.generate_maze(0, 0);
+        let mut second = Graph::with_seed(10, 10, 42);
+        second.generate_maze(0, 0);
+        assert_eq!(first.draw_maze(), second.draw_maze());
+    }
+
+    #[test]
+    fn test_different_seeds_different_mazes()
+    {
+        let mut first = Graph::with_seed(10, 10, 1);
+        first.generate_maze(0, 0);
+        let mut second = Graph::with_seed(10, 10, 2);
+        second.generate_maze(0, 0);
+        assert_ne!(first.draw_maze(), second.draw_maze());
     }
 
-    pub fn update_bot_position(&mut self, new_x: usize, new_y: usize)
+    #[test]
+    fn test_generate_huge_maze()
     {
-        let mut is_on_exit = false;
-        let mut is_on_key = false;
+        let mut graph = Graph::with_seed(1000, 1000, 3);
+        graph.generate_maze(0, 0);
+        assert!(graph.visited.iter().all(|visited| *visited));
 
-        if self.map[new_y][new_x] == WALL 
+        // a perfect maze is a spanning tree, so it has exactly one passage less than cells
+        let passages = graph.cells.iter().filter(|cell| !cell.right).count() + graph.cells.iter().filter(|cell| !cell.down).count();
+        assert_eq!(passages, graph.width * graph.height - 1);
+    }
+
+    #[test]
+    fn test_every_algorithm_makes_perfect_maze()
+    {
+        for algorithm in Algorithm::ALL
         {
-            self.ai.update_direction(false);
-            return;
-        } 
-        else if self.map[new_y][new_x] == PLAYER
+            for seed in 0..5
+            {
+                let mut graph = Graph::with_seed(12, 12, seed);
+                algorithm.generate(&mut graph);
+                assert!(is_perfect_maze(&graph), "{:?} with seed {}", algorithm, seed);
+                assert!(graph.visited.iter().all(|visited| *visited), "{:?} with seed {}", algorithm, seed);
+            }
+        }
+    }
+
+    #[test]
+    fn test_every_algorithm_is_reproducible()
+    {
+        for algorithm in Algorithm::ALL
         {
-            self.game_state = State::Credits(String::from("Game Over!"));
-            let ten_millis = time::Duration::from_secs_f32(0.5);
-            thread::sleep(ten_millis);
-            return;
-        } 
-        else if self.map[new_y][new_x] == EXIT
+            let mut first = Graph::with_seed(10, 10, 11);
+            algorithm.generate(&mut first);
+            let mut second = Graph::with_seed(10, 10, 11);
+            algorithm.generate(&mut second);
+            assert_eq!(first.draw_maze(), second.draw_maze(), "{:?}", algorithm);
+        }
+    }
+
+    #[test]
+    fn test_tall_maze()
+    {
+        for algorithm in Algorithm::ALL
         {
-            is_on_exit = true;
+            let mut graph = Graph::with_seed(4, 15, 5);
+            algorithm.generate(&mut graph);
+            assert!(is_perfect_maze(&graph), "{:?}", algorithm);
+            assert_map_matches_graph(&graph);
         }
-        else if self.map[new_y][new_x] == KEY
+    }
+
+    #[test]
+    fn test_wide_maze()
+    {
+        for algorithm in Algorithm::ALL
         {
-            is_on_key = true;
+            let mut graph = Graph::with_seed(15, 4, 5);
+            algorithm.generate(&mut graph);
+            assert!(is_perfect_maze(&graph), "{:?}", algorithm);
+            assert_map_matches_graph(&graph);
         }
+    }
+
+    #[test]
+    fn test_no_loops_keeps_perfect_maze()
+    {
+        let mut graph = Graph::with_seed(12, 9, 4);
+        graph.generate_maze(0, 0);
+        graph.braid(0.0);
+        assert!(is_perfect_maze(&graph));
+        assert_map_matches_graph(&graph);
+    }
 
-        match (self.ai.is_on_exit, self.ai.is_on_key)
+    #[test]
+    fn test_full_braid_leaves_no_dead_ends()
+    {
+        for algorithm in Algorithm::ALL
         {
-            (false, true) => {self.map[self.ai.y][self.ai.x] = KEY;},
-            (true, false) => {self.map[self.ai.y][self.ai.x] = EXIT;},
-            (false, false) => {self.map[self.ai.y][self.ai.x] = FLOOR;},
-            _ => ()
+            let mut graph = Graph::with_seed(12, 9, 4);
+            algorithm.generate(&mut graph);
+            graph.braid(1.0);
+            assert!(graph.cells.iter().all(|cell| !graph.is_dead_end(cell.x, cell.y)), "{:?}", algorithm);
+            assert_map_matches_graph(&graph);
         }
-        self.ai.update_position(new_x, new_y, is_on_exit, is_on_key);
-        self.map[self.ai.y][self.ai.x] = BOT;
     }
 
-    pub fn restart_timer(&mut self)
+    #[test]
+    fn test_loop_density_controls_dead_ends()
     {
-        self.time_until_bot_speed_up = 1.0;
+        let dead_ends = |loop_density: f64|
+        {
+            let mut graph = Graph::with_seed(30, 30, 8);
+            graph.generate_maze(0, 0);
+            graph.braid(loop_density);
+            graph.cells.iter().filter(|cell| graph.is_dead_end(cell.x, cell.y)).count()
+        };
+        assert!(dead_ends(0.0) > dead_ends(0.5));
+        assert!(dead_ends(0.5) > dead_ends(1.0));
     }
-}
 
-fn generate_location(map: &Vec<Vec<char>>) -> Point2<usize>
-{
-    let mut rng = rand::thread_rng();
-    let mut x = rng.gen_range(0..map[0].len());
-    let mut y = rng.gen_range(0..map.len());
-    while map[y][x] != FLOOR
+    #[test]
+    fn test_window_size_follows_maze_size()
     {
-        x = rng.gen_range(0..map[0].len());
-        y = rng.gen_range(0..map.len());
+        let mut settings = Settings::with_seed(0);
+        settings.width = 20;
+        settings.height = 5;
+        assert_eq!(game::window_size(&settings), (game::MAX_WINDOW_SIZE.0, 11.0 * 45.0));
+        settings.width = 5;
+        assert_eq!(game::window_size(&settings), (11.0 * 45.0, 11.0 * 45.0));
     }
-    Point2 { x: x, y: y }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_parse_algorithm_name()
+    {
+        assert_eq!("hunt-and-kill".parse::<Algorithm>(), Ok(Algorithm::HuntAndKill));
+        assert_eq!("Aldous_Broder".parse::<Algorithm>(), Ok(Algorithm::AldousBroder));
+        assert!("labyrinth".parse::<Algorithm>().is_err());
+    }
 
     #[test]
-    fn test_speed_up() 
+    fn test_same_seed_same_placement()
     {
-        let mut bot = Bot::new(1,1);
-        let old_speed = bot.time_for_step;
-        bot.speed_up();
-        assert!(old_speed>bot.time_for_step)
+        let first = Simulation::new(Settings::with_seed(7)).unwrap();
+        let second = Simulation::new(Settings::with_seed(7)).unwrap();
+        assert_eq!(first.map, second.map);
+        assert_eq!((first.bots[0].x, first.bots[0].y), (second.bots[0].x, second.bots[0].y));
+    }
+
+    #[test]
+    fn test_step_ignored_before_start()
+    {
+        let mut game = Simulation::new(Settings::with_seed(1)).unwrap();
+        game.step(Input::Move(Direction::Down), 1.0);
+        game.step(Input::Move(Direction::Right), 1.0);
+        assert_eq!((game.player.x, game.player.y), (1, 1));
+        assert_eq!(game.game_state, State::Start);
+    }
+
+    #[test]
+    fn test_bot_speeds_up_every_second()
+    {
+        let mut game = new_simulation(1);
+        let old_speed = game.bots[0].time_for_step;
+        game.step(Input::Idle, 1.0);
+        assert!(old_speed > game.bots[0].time_for_step);
+        assert_eq!(game.time_until_bot_speed_up, 1.0);
     }
 
     #[test]
     fn test_not_go_throught_wall()
     {
-        let mut game = MazeGameMock::new().unwrap();
-        let old_player_data = game.player.clone();
-        game.update_player_position(old_player_data.x-1, old_player_data.y, event::KeyCode::W);
+        let mut game = new_simulation(2);
+        let old_player_data = game.player;
+        game.step(Input::Move(Direction::Left), 0.0);
         assert_eq!(old_player_data.x, game.player.x);
         assert_eq!(old_player_data.y, game.player.y);
-        assert_eq!(event::KeyCode::W, game.player.direction);
+        assert_eq!(Direction::Left, game.player.direction);
     }
 
     #[test]
     fn test_making_step()
     {
-        let mut game = MazeGameMock::new().unwrap();
-        let old_player_data = game.player.clone();
-        if game.map[old_player_data.y+1][old_player_data.x] == FLOOR
+        let mut game = new_simulation(3);
+        let old_player_data = game.player;
+        let (far_x, far_y) = (game.map.width() - 2, game.map.height() - 3);
+        place_bot(&mut game, far_x, far_y);
+        if game.map[(old_player_data.x, old_player_data.y+1)] == Tile::Floor
         {
-            game.update_player_position(old_player_data.x, old_player_data.y+1, event::KeyCode::S);
-            assert_eq!(game.map[old_player_data.y+1][old_player_data.x], PLAYER);
+            game.step(Input::Move(Direction::Down), 0.0);
             assert_eq!(old_player_data.x, game.player.x);
-            assert_ne!(old_player_data.y, game.player.y);
+            assert_eq!(old_player_data.y+1, game.player.y);
         }
-        else if game.map[old_player_data.y][old_player_data.x+1] == FLOOR
+        else if game.map[(old_player_data.x+1, old_player_data.y)] == Tile::Floor
         {
-            game.update_player_position(old_player_data.x+1, old_player_data.y, event::KeyCode::D);
-            assert_eq!(game.map[old_player_data.y][old_player_data.x+1], PLAYER);
-            assert_ne!(old_player_data.x, game.player.x);
+            game.step(Input::Move(Direction::Right), 0.0);
+            assert_eq!(old_player_data.x+1, game.player.x);
             assert_eq!(old_player_data.y, game.player.y);
         }
     }
@@ -202,52 +350,1703 @@ mod tests {
     #[test]
     fn test_face_bot()
     {
-        let mut game = MazeGameMock::new().unwrap();
-        game.ai.x = game.player.x + 1;
-        game.ai.y = game.player.y;
-        game.map[game.ai.y][game.ai.x] = BOT;
-        game.update_player_position(game.player.x+1, game.player.y, event::KeyCode::D);
-        
+        let mut game = new_simulation(4);
+        let (next_x, next_y) = (game.player.x + 1, game.player.y);
+        game.map.set(next_x, next_y, Tile::Floor);
+        place_bot(&mut game, next_x, next_y);
+        game.step(Input::Move(Direction::Right), 0.0);
+
         assert_eq!(game.game_state, State::Credits(String::from("Game Over!")));
     }
 
+    // puts the player on the tile next to the exit, returns the direction of the exit
+    fn stand_before_exit(game: &mut Simulation) -> Direction
+    {
+        place_bot(game, 1, 1);
+        let (x, y) = game.map.walkable_neighbors(game.exit.0, game.exit.1).next().unwrap();
+        game.player.update(x, y);
+        Direction::ALL.into_iter().find(|direction| direction.step(x, y) == Some(game.exit)).unwrap()
+    }
+
     #[test]
     fn test_get_to_exit_with_key()
     {
-        let mut game = MazeGameMock::new().unwrap();
-        game.game_state = State::MainState;
-        game.player.y = game.map.len() - 2;
-        game.player.x = game.map[game.player.y].len() - 3;
-        game.map[game.player.y][game.player.x] = PLAYER;
+        let mut game = new_simulation(5);
+        let towards_exit = stand_before_exit(&mut game);
         game.player.has_key = true;
-        game.update_player_position(game.player.x+1, game.player.y, event::KeyCode::D);
-        
+        game.step(Input::Move(towards_exit), 0.0);
+
         assert_eq!(game.game_state, State::Credits(String::from("You found the exit!")));
     }
 
     #[test]
     fn test_get_to_exit_without_key()
     {
-        let mut game = MazeGameMock::new().unwrap();
-        game.game_state = State::MainState;
-        game.player.y = game.map.len() - 2;
-        game.player.x = game.map[game.player.y].len() - 3;
-        game.map[game.player.y][game.player.x] = PLAYER;
-        game.update_player_position(game.player.x+1, game.player.y, event::KeyCode::D);
-        
+        let mut game = new_simulation(6);
+        let towards_exit = stand_before_exit(&mut game);
+        game.step(Input::Move(towards_exit), 0.0);
+
         assert_eq!(game.game_state, State::MainState);
+        assert_eq!(game.map[(game.player.x, game.player.y)], Tile::Door);
     }
 
     #[test]
     fn test_pick_up_key()
     {
-        let mut game = MazeGameMock::new().unwrap();
-        game.map[game.player.y][game.player.x+1] = KEY;
-        game.game_state = State::MainState;
-        assert_eq!(game.player.has_key, false);
-        game.update_player_position(game.player.x+1, game.player.y, event::KeyCode::D);
-        game.update_player_position(game.player.x-1, game.player.y, event::KeyCode::A);
-        assert_eq!(game.map[game.player.y][game.player.x+1], FLOOR);
-        assert_eq!(game.player.has_key, true);
+        let mut game = new_simulation(7);
+        let (far_x, far_y) = (game.map.width() - 2, game.map.height() - 3);
+        place_bot(&mut game, far_x, far_y);
+        game.map.set(game.player.x+1, game.player.y, Tile::Key);
+        assert!(!game.player.has_key);
+        game.step(Input::Move(Direction::Right), 0.0);
+        let time_for_step = game.player.time_for_step;
+        game.step(Input::Move(Direction::Left), time_for_step);
+        assert_eq!(game.map[(game.player.x+1, game.player.y)], Tile::Floor);
+        assert!(game.player.has_key);
+    }
+
+    #[test]
+    fn test_bot_does_not_erase_key()
+    {
+        let mut game = new_simulation(8);
+        let key = game.map.positions().find(|position| game.map[*position] == Tile::Key).unwrap();
+        place_bot(&mut game, key.0, key.1);
+        let next = game.map.walkable_neighbors(key.0, key.1).find(|position| *position != (game.player.x, game.player.y)).unwrap();
+        game.update_bot_position(0, next.0, next.1);
+        assert_eq!((game.bots[0].x, game.bots[0].y), next);
+        assert_eq!(game.map[key], Tile::Key);
+    }
+
+    #[test]
+    fn test_grid_from_chars()
+    {
+        let grid = Grid::from_chars(&[vec!['W', 'W', 'W'], vec!['W', '.', 'V'], vec!['W', 'K', 'W']]);
+        assert_eq!((grid.width(), grid.height()), (3, 3));
+        assert_eq!(grid[(1, 1)], Tile::Floor);
+        assert_eq!(grid[(2, 1)], Tile::Door);
+        assert_eq!(grid[(1, 2)], Tile::Key);
+        assert_eq!(grid.get(3, 1), None);
+        assert!(!grid.is_walkable(0, 0));
+        assert!(!grid.is_walkable(5, 5));
+    }
+
+    #[test]
+    fn test_grid_from_chars_reads_coloured_tiles()
+    {
+        let grid = grid_from_rows(&["rgbp", "1234"]);
+        for (x, colour) in Colour::ALL.into_iter().enumerate()
+        {
+            assert_eq!(grid[(x, 0)], Tile::LockedDoor(colour));
+            assert_eq!(grid[(x, 1)], Tile::ColouredKey(colour));
+        }
+        // 'P' used to mark the player and is no door
+        assert_eq!(grid_from_rows(&["P"])[(0, 0)], Tile::Floor);
+    }
+
+    #[test]
+    fn test_grid_neighbors()
+    {
+        let grid = Grid::new(3, 2, 0);
+        assert_eq!(grid.neighbors(0, 0).collect::<Vec<_>>(), vec![(0, 1), (1, 0)]);
+        assert_eq!(grid.neighbors(1, 1).collect::<Vec<_>>(), vec![(1, 0), (0, 1), (2, 1)]);
+        assert_eq!(grid.neighbors(2, 1).count(), 2);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_direction_opposite_and_turns()
+    {
+        for direction in Direction::ALL
+        {
+            assert_eq!(direction.opposite().opposite(), direction);
+            assert_eq!(direction.turn_left().turn_right(), direction);
+            assert_eq!(direction.turn_right().turn_right(), direction.opposite());
+            let (dx, dy) = direction.delta();
+            let (opposite_dx, opposite_dy) = direction.opposite().delta();
+            assert_eq!((dx + opposite_dx, dy + opposite_dy), (0, 0));
+        }
+        assert_eq!(Direction::Up.turn_left(), Direction::Left);
+        assert_eq!(Direction::Up.turn_right(), Direction::Right);
+    }
+
+    #[test]
+    fn test_direction_step()
+    {
+        assert_eq!(Direction::Right.step(3, 4), Some((4, 4)));
+        assert_eq!(Direction::Up.step(3, 4), Some((3, 3)));
+        assert_eq!(Direction::Left.step(0, 4), None);
+        assert_eq!(Direction::Up.step(3, 0), None);
+        assert_eq!(Direction::between((2, 2), (2, 3)), Some(Direction::Down));
+        assert_eq!(Direction::between((2, 2), (4, 2)), None);
+    }
+
+    #[test]
+    fn test_find_path_shortest()
+    {
+        let grid = grid_from_rows(&[
+            "WWWWWWW",
+            "W.....W",
+            "W.WWW.W",
+            "W...W.W",
+            "WWW.W.W",
+            "W.....W",
+            "WWWWWWW"
+        ]);
+        let path = pathfinding::find_path(&grid, (1, 1), None, (5, 5), 0).unwrap();
+        assert_eq!(path.len(), 8);
+        assert_eq!(path.last(), Some(&(5, 5)));
+        let mut previous = (1, 1);
+        for next in path
+        {
+            assert!(grid.is_walkable(next.0, next.1));
+            assert!(Direction::between(previous, next).is_some());
+            previous = next;
+        }
+    }
+
+    #[test]
+    fn test_find_path_turn_cost_prefers_straight_lines()
+    {
+        let grid = grid_from_rows(&[
+            "WWWWWW",
+            "W....W",
+            "W....W",
+            "W....W",
+            "W....W",
+            "WWWWWW"
+        ]);
+        let path = pathfinding::find_path(&grid, (1, 1), Some(Direction::Right), (4, 4), pathfinding::STEP_COST).unwrap();
+        assert_eq!(path.len(), 6);
+        assert_eq!(count_turns((1, 1), &path), 1);
+        assert_eq!(path[0], (2, 1));
+    }
+
+    #[test]
+    fn test_find_path_unreachable()
+    {
+        let grid = grid_from_rows(&[
+            "WWWWW",
+            "W.W.W",
+            "WWWWW"
+        ]);
+        assert_eq!(pathfinding::find_path(&grid, (1, 1), None, (3, 1), 0), None);
+        assert_eq!(pathfinding::find_path(&grid, (1, 1), None, (2, 1), 0), None);
+        assert_eq!(pathfinding::find_path(&grid, (1, 1), None, (1, 1), 0), Some(Vec::new()));
+    }
+
+    #[test]
+    fn test_hunter_catches_idle_player()
+    {
+        let mut settings = Settings::with_seed(9);
+        settings.bots = vec![BotConfig::new(Behaviour::hunter())];
+        let mut game = Simulation::new(settings).unwrap();
+        game.start();
+        for _ in 0..60*120
+        {
+            game.step(Input::Idle, 1.0 / 60.0);
+            if game.game_state != State::MainState
+            {
+                break;
+            }
+        }
+        assert_eq!(game.game_state, State::Credits(String::from("Game Over!")));
+    }
+
+    #[test]
+    fn test_hunter_walks_its_path()
+    {
+        let grid = grid_from_rows(&[
+            "WWWWWWW",
+            "W.....W",
+            "WWWWWWW"
+        ]);
+        let bot = Bot::new(1, 1);
+        let mut hunter = Hunter::new(0.5, pathfinding::STEP_COST / 2);
+        let view = bot.view(&grid, Some(((5, 1), Direction::Left)), 0.0);
+        assert_eq!(hunter.next_move(&view, &mut StdRng::seed_from_u64(0)), Some(Direction::Right));
+        assert_eq!(hunter.path.len(), 3);
+    }
+
+    #[test]
+    fn test_parse_bot_behaviour()
+    {
+        assert_eq!("Wanderer".parse::<Behaviour>(), Ok(Behaviour::Wanderer));
+        assert_eq!("hunter".parse::<Behaviour>(), Ok(Behaviour::hunter()));
+        assert!("ghost".parse::<Behaviour>().is_err());
+    }
+
+    fn open_room() -> Grid<Tile>
+    {
+        grid_from_rows(&[
+            "WWWWWWWWW",
+            "W.......W",
+            "W.......W",
+            "W.......W",
+            "W.......W",
+            "W.......W",
+            "W.......W",
+            "W.......W",
+            "WWWWWWWWW"
+        ])
+    }
+
+    #[test]
+    fn test_look_for_player_every_direction()
+    {
+        let grid = open_room();
+        for (player, expected) in [((4, 1), Direction::Up), ((4, 7), Direction::Down), ((1, 4), Direction::Left), ((7, 4), Direction::Right)]
+        {
+            let mut bot = Bot::new(4, 4);
+            bot.direction = expected.opposite();
+            look_for_player(&mut bot, &grid, player);
+            assert_eq!(bot.direction, expected, "player at {:?}", player);
+        }
+    }
+
+    #[test]
+    fn test_look_for_player_ignores_player_off_axis()
+    {
+        let grid = open_room();
+        let mut bot = Bot::new(4, 4);
+        bot.direction = Direction::Left;
+        assert!(bot.can_see(&grid, (6, 6)));
+        look_for_player(&mut bot, &grid, (6, 6));
+        assert_eq!(bot.direction, Direction::Left);
+    }
+
+    #[test]
+    fn test_walls_block_sight()
+    {
+        let mut grid = open_room();
+        grid.set(4, 2, Tile::Wall);
+        grid.set(6, 4, Tile::Wall);
+        let mut bot = Bot::new(4, 4);
+        bot.direction = Direction::Left;
+        assert!(!bot.can_see(&grid, (4, 1)));
+        assert!(!bot.can_see(&grid, (7, 4)));
+        assert!(bot.can_see(&grid, (4, 7)));
+        look_for_player(&mut bot, &grid, (4, 1));
+        assert_eq!(bot.direction, Direction::Left);
+    }
+
+    #[test]
+    fn test_view_range()
+    {
+        let grid = open_room();
+        let mut bot = Bot::new(1, 1);
+        bot.vision = Vision{range: 3, field_of_view: None};
+        assert!(bot.can_see(&grid, (4, 1)));
+        assert!(!bot.can_see(&grid, (5, 1)));
+        assert!(bot.can_see(&grid, (3, 3)));
+        assert!(!bot.can_see(&grid, (4, 4)));
+    }
+
+    #[test]
+    fn test_field_of_view_follows_facing()
+    {
+        let grid = open_room();
+        let mut bot = Bot::new(4, 4);
+        bot.vision = Vision{range: 10, field_of_view: Some(90.0)};
+        for direction in Direction::ALL
+        {
+            bot.direction = direction;
+            let (dx, dy) = direction.delta();
+            let ahead = ((4 + 2*dx) as usize, (4 + 2*dy) as usize);
+            let behind = ((4 - 2*dx) as usize, (4 - 2*dy) as usize);
+            let (side_x, side_y) = direction.turn_left().delta();
+            let side = ((4 + 2*side_x) as usize, (4 + 2*side_y) as usize);
+            assert!(bot.can_see(&grid, ahead), "{:?}", direction);
+            assert!(!bot.can_see(&grid, behind), "{:?}", direction);
+            assert!(!bot.can_see(&grid, side), "{:?}", direction);
+        }
+        bot.direction = Direction::Right;
+        assert!(bot.can_see(&grid, (6, 5)));
+    }
+
+    #[test]
+    fn test_sight_at_grid_edges()
+    {
+        let grid = grid_from_rows(&[
+            "...",
+            "...",
+            "..."
+        ]);
+        let mut bot = Bot::new(0, 0);
+        look_for_player(&mut bot, &grid, (2, 0));
+        assert_eq!(bot.direction, Direction::Right);
+        look_for_player(&mut bot, &grid, (0, 2));
+        assert_eq!(bot.direction, Direction::Down);
+
+        let mut bot = Bot::new(2, 2);
+        look_for_player(&mut bot, &grid, (0, 2));
+        assert_eq!(bot.direction, Direction::Left);
+        look_for_player(&mut bot, &grid, (2, 0));
+        assert_eq!(bot.direction, Direction::Up);
+        assert!(!bot.can_see(&grid, (5, 2)));
+    }
+
+    // one bot step as the simulation runs it, the player stays where it is
+    fn tick_wanderer(bot: &mut Bot, grid: &Grid<Tile>, player: (usize, usize), dt: f32, rng: &mut StdRng)
+    {
+        let knows_player = bot.perceive(grid, player, dt);
+        let player_view = if knows_player { Some((player, Direction::Down)) } else { None };
+        let next = bot.think(grid, player_view, dt, rng).and_then(|direction| direction.step(bot.x, bot.y));
+        if let Some((x, y)) = next
+        {
+            if grid.is_walkable(x, y) && (x, y) != player
+            {
+                bot.update_position(x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_bot_starts_chase_when_it_sees_player()
+    {
+        let grid = open_room();
+        let mut bot = Bot::new(1, 1);
+        assert_eq!(bot.state, BotState::Patrol);
+        bot.perceive(&grid, (5, 1), 0.1);
+        assert_eq!(bot.state, BotState::Chase);
+        assert_eq!(bot.last_seen, Some((5, 1)));
+    }
+
+    #[test]
+    fn test_bot_goes_to_last_known_position()
+    {
+        let grid = grid_from_rows(&[
+            "WWWWWWWWW",
+            "W.......W",
+            "WWWWWWWWW",
+            "W.WWWWWWW",
+            "WWWWWWWWW"
+        ]);
+        let mut bot = Bot::new(1, 1);
+        let mut rng = StdRng::seed_from_u64(0);
+        bot.perceive(&grid, (7, 1), 0.1);
+        // the player is gone from the corridor, somewhere the bot can't see
+        let player = (1, 3);
+        for _ in 0..6
+        {
+            tick_wanderer(&mut bot, &grid, player, 0.1, &mut rng);
+        }
+        assert_eq!((bot.x, bot.y), (7, 1));
+        assert_eq!(bot.last_seen, Some((7, 1)));
+        assert_ne!(bot.state, BotState::Patrol);
+    }
+
+    #[test]
+    fn test_bot_searches_then_patrols()
+    {
+        let grid = open_room();
+        let mut bot = Bot::new(4, 4);
+        bot.vision = Vision{range: 1, field_of_view: None};
+        bot.search_duration = 2.0;
+        bot.brain = Box::new(Wanderer::new(2));
+        bot.perceive(&grid, (4, 4), 0.1);
+        let hidden_player = (7, 7);
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut searched = 0.0;
+        while bot.state != BotState::Patrol
+        {
+            tick_wanderer(&mut bot, &grid, hidden_player, 0.1, &mut rng);
+            // the step right after the search ends is already a patrol step
+            assert!(bot.state == BotState::Patrol || bot.x.abs_diff(4) + bot.y.abs_diff(4) <= 2);
+            searched += 0.1;
+            assert!(searched < 3.0);
+        }
+        assert!(searched >= 1.9);
+        assert_eq!(bot.last_seen, None);
+    }
+
+    fn all_profiles() -> Vec<BotConfig>
+    {
+        vec![
+            BotConfig::new(Behaviour::Wanderer),
+            BotConfig{behaviour: Behaviour::hunter(), time_for_step: Some(0.3), brain: None},
+            BotConfig::new(Behaviour::ambusher()),
+            BotConfig::new(Behaviour::guard())
+        ]
+    }
+
+    #[test]
+    fn test_parse_bot_config()
+    {
+        assert_eq!("guard".parse::<BotConfig>(), Ok(BotConfig::new(Behaviour::guard())));
+        assert_eq!("chaser:0.2".parse::<BotConfig>(), Ok(BotConfig{behaviour: Behaviour::hunter(), time_for_step: Some(0.2), brain: None}));
+        assert!("ambusher:fast".parse::<BotConfig>().is_err());
+        assert!("hunter:0".parse::<BotConfig>().is_err());
+    }
+
+    #[test]
+    fn test_one_bot_per_config()
+    {
+        let mut settings = Settings::with_seed(12);
+        settings.bots = all_profiles();
+        let game = Simulation::new(settings).unwrap();
+        assert_eq!(game.bots.len(), 4);
+        assert_eq!(game.bots[1].time_for_step, 0.3);
+        let key = game.map.positions().find(|position| game.map[*position] == Tile::Key).unwrap();
+        assert_eq!(game.bots[3].post, Some(key));
+        for (index, bot) in game.bots.iter().enumerate()
+        {
+            assert_eq!(game.map[(bot.x, bot.y)], Tile::Floor);
+            assert_ne!((bot.x, bot.y), (game.player.x, game.player.y));
+            assert!(game.bots[..index].iter().all(|other| (other.x, other.y) != (bot.x, bot.y)));
+        }
+    }
+
+    #[test]
+    fn test_bots_never_overlap()
+    {
+        for seed in 0..5
+        {
+            let mut settings = Settings::with_seed(seed);
+            settings.bots = all_profiles();
+            let mut game = Simulation::new(settings).unwrap();
+            game.start();
+            for _ in 0..60*10
+            {
+                game.step(Input::Idle, 1.0 / 60.0);
+                for (index, bot) in game.bots.iter().enumerate()
+                {
+                    assert!(game.bots[..index].iter().all(|other| (other.x, other.y) != (bot.x, bot.y)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_bot_waits_for_other_bot()
+    {
+        let mut settings = Settings::with_seed(3);
+        settings.bots = vec![BotConfig::new(Behaviour::Wanderer), BotConfig::new(Behaviour::Wanderer)];
+        let mut game = Simulation::new(settings).unwrap();
+        let (x, y) = (game.bots[1].x, game.bots[1].y);
+        let next = game.map.walkable_neighbors(x, y).next().unwrap();
+        game.bots[0].update_position(next.0, next.1);
+        game.update_bot_position(1, next.0, next.1);
+        assert_eq!((game.bots[1].x, game.bots[1].y), (x, y));
+    }
+
+    #[test]
+    fn test_ambusher_aims_ahead_of_player()
+    {
+        let grid = grid_from_rows(&[
+            "WWWWWWWWWWWWWWW",
+            "W.............W",
+            "WWWWWWWWWWWWWWW"
+        ]);
+        let bot = Bot::new(13, 1);
+        let ambusher = Ambusher::new(3, 0.5, 0);
+        assert_eq!(ambusher.target(&bot.view(&grid, Some(((1, 1), Direction::Right)), 0.0)), Some((4, 1)));
+        // the wall cuts the lookahead short
+        assert_eq!(ambusher.target(&bot.view(&grid, Some(((2, 1), Direction::Left)), 0.0)), Some((1, 1)));
+        // close enough to go for the player directly
+        assert_eq!(ambusher.target(&bot.view(&grid, Some(((11, 1), Direction::Left)), 0.0)), Some((11, 1)));
+        assert_eq!(ambusher.target(&bot.view(&grid, None, 0.0)), None);
+    }
+
+    #[test]
+    fn test_ambusher_only_knows_a_player_it_has_seen()
+    {
+        let grid = open_room();
+        let mut bot = Bot::with_behaviour(1, 1, Behaviour::ambusher());
+        bot.vision = Vision{range: 2, field_of_view: None};
+        assert!(!bot.perceive(&grid, (7, 7), 0.1));
+        assert_eq!((bot.state, bot.last_seen), (BotState::Patrol, None));
+        let mut ambusher = Ambusher::new(3, 0.5, 0);
+        assert!(ambusher.next_move(&bot.view(&grid, None, 0.0), &mut StdRng::seed_from_u64(0)).is_some());
+        assert!(ambusher.hunter.path.is_empty());
+
+        assert!(bot.perceive(&grid, (1, 3), 0.1));
+        assert_eq!(bot.last_seen, Some((1, 3)));
+    }
+
+    #[test]
+    fn test_guard_stays_near_post()
+    {
+        let grid = open_room();
+        let mut bot = Bot::with_behaviour(6, 6, Behaviour::Guard{radius: 2});
+        bot.post = Some((6, 6));
+        // the player is in plain sight but far from the post
+        bot.perceive(&grid, (1, 1), 0.1);
+        assert_eq!(bot.state, BotState::Patrol);
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..50
+        {
+            tick_wanderer(&mut bot, &grid, (1, 1), 0.1, &mut rng);
+            assert!(bot.x.abs_diff(6) + bot.y.abs_diff(6) <= 2);
+        }
+        bot.perceive(&grid, (6, 4), 0.1);
+        assert_eq!(bot.state, BotState::Chase);
+    }
+
+    // a brain written outside the crate, it always walks right
+    struct RightWalker;
+
+    impl BotBrain for RightWalker
+    {
+        fn next_move(&mut self, _view: &brain::WorldView, _rng: &mut StdRng) -> Option<Direction>
+        {
+            Some(Direction::Right)
+        }
+    }
+
+    #[test]
+    fn test_custom_brain_steers_bot()
+    {
+        let mut game = new_simulation(2);
+        let grid = open_room();
+        game.map = grid;
+        game.player.update(1, 7);
+        place_bot(&mut game, 1, 1);
+        game.bots[0].brain = Box::new(RightWalker);
+        for _ in 0..20
+        {
+            game.step(Input::Idle, 0.1);
+        }
+        // stopped by the wall, never walked anywhere else
+        assert_eq!((game.bots[0].x, game.bots[0].y), (7, 1));
+        assert_eq!(game.game_state, State::MainState);
+    }
+
+    #[test]
+    fn test_custom_brain_from_settings()
+    {
+        let mut settings = Settings::with_seed(2);
+        settings.difficulty = Difficulty::Hard;
+        settings.bots = vec![BotConfig::custom(BrainFactory::new(|| Box::new(RightWalker)))];
+        let mut game = Simulation::new(settings).unwrap();
+        let key = game.map.positions().find(|position| game.map[*position] == Tile::Key).unwrap();
+        let grid = open_room();
+        for bot in &mut game.bots
+        {
+            assert_eq!(bot.behaviour, Behaviour::Custom);
+            assert_eq!(bot.post, Some(key));
+            assert!(!bot.route.is_empty());
+            bot.update_position(1, 1);
+            assert_eq!(bot.think(&grid, None, 0.1, &mut StdRng::seed_from_u64(0)), Some(Direction::Right));
+        }
+        assert_eq!(game.bots.len(), 2);
+    }
+
+    #[test]
+    fn test_random_walker_follows_corridor()
+    {
+        let grid = grid_from_rows(&[
+            "WWWWWWW",
+            "W.....W",
+            "WWWWWWW"
+        ]);
+        let mut bot = Bot::new(3, 1);
+        bot.direction = Direction::Left;
+        let mut rng = StdRng::seed_from_u64(0);
+        assert_eq!(RandomWalker.next_move(&bot.view(&grid, None, 0.1), &mut rng), Some(Direction::Left));
+        // turns back only at the dead end
+        bot.update_position(1, 1);
+        assert_eq!(RandomWalker.next_move(&bot.view(&grid, None, 0.1), &mut rng), Some(Direction::Right));
+        // heads for a player it sees
+        bot.update_position(3, 1);
+        assert_eq!(RandomWalker.next_move(&bot.view(&grid, Some(((5, 1), Direction::Up)), 0.1), &mut rng), Some(Direction::Right));
+    }
+
+    #[test]
+    fn test_same_seed_same_game()
+    {
+        let mut settings = Settings::with_seed(21);
+        settings.bots = all_profiles();
+        let mut first = Simulation::new(settings.clone()).unwrap();
+        let mut second = Simulation::new(settings).unwrap();
+        first.start();
+        second.start();
+        for _ in 0..60*5
+        {
+            first.step(Input::Idle, 1.0 / 60.0);
+            second.step(Input::Idle, 1.0 / 60.0);
+            let first_bots: Vec<(usize, usize)> = first.bots.iter().map(|bot| (bot.x, bot.y)).collect();
+            let second_bots: Vec<(usize, usize)> = second.bots.iter().map(|bot| (bot.x, bot.y)).collect();
+            assert_eq!(first_bots, second_bots);
+        }
+    }
+
+    #[test]
+    fn test_behaviour_builds_its_brain()
+    {
+        let grid = open_room();
+        let mut hunter = Bot::with_behaviour(1, 1, "chaser".parse::<Behaviour>().unwrap());
+        assert_eq!(hunter.think(&grid, Some(((4, 1), Direction::Down)), 0.1, &mut StdRng::seed_from_u64(0)), Some(Direction::Right));
+    }
+
+    #[test]
+    fn test_hunter_only_knows_a_player_it_has_seen()
+    {
+        let grid = open_room();
+        let mut bot = Bot::with_behaviour(1, 1, Behaviour::hunter());
+        bot.vision = Vision{range: 2, field_of_view: None};
+        assert!(!bot.perceive(&grid, (7, 7), 0.1));
+        assert_eq!((bot.state, bot.last_seen), (BotState::Patrol, None));
+        // without a target it patrols instead of standing still
+        let mut hunter = Hunter::new(0.5, 0);
+        assert!(hunter.next_move(&bot.view(&grid, None, 0.0), &mut StdRng::seed_from_u64(0)).is_some());
+        assert!(hunter.path.is_empty());
+
+        assert!(bot.perceive(&grid, (3, 1), 0.1));
+        assert_eq!(bot.last_seen, Some((3, 1)));
+    }
+
+    #[test]
+    fn test_distances_count_steps()
+    {
+        let grid = grid_from_rows(&[
+            "WWWWW",
+            "W...W",
+            "W.W.W",
+            "W.WWW",
+            "WWWWW"
+        ]);
+        let distances = pathfinding::distances(&grid, (1, 1));
+        assert_eq!(distances[(1, 1)], Some(0));
+        assert_eq!(distances[(3, 2)], Some(3));
+        assert_eq!(distances[(1, 3)], Some(2));
+        assert_eq!(distances[(2, 2)], None);
+        assert_eq!(grid.dead_ends().collect::<Vec<(usize, usize)>>(), vec![(3, 2), (1, 3)]);
+    }
+
+    #[test]
+    fn test_generated_route_visits_dead_ends()
+    {
+        let mut graph = Graph::with_seed(8, 8, 5);
+        graph.generate_maze(0, 0);
+        let grid = Grid::from_chars(&graph.draw_maze());
+        let route = brain::generate_route(&grid, (1, 1), 3);
+        assert_eq!(route.len(), 4);
+        assert_eq!(route[0], (1, 1));
+        let distances = pathfinding::distances(&grid, (1, 1));
+        for waypoint in &route[1..]
+        {
+            assert_eq!(grid.walkable_neighbors(waypoint.0, waypoint.1).count(), 1);
+            assert!(distances[*waypoint].is_some());
+        }
+
+        // without any dead end the route still has somewhere to go
+        let route = brain::generate_route(&open_room(), (1, 1), 3);
+        assert!(route.len() >= 3);
+    }
+
+    // walks a patroller for `steps` steps with the player out of sight and returns the tiles it stood on
+    fn walk_patrol(bot: &mut Bot, grid: &Grid<Tile>, steps: usize, rng: &mut StdRng) -> Vec<(usize, usize)>
+    {
+        let mut visited = Vec::new();
+        for _ in 0..steps
+        {
+            tick_wanderer(bot, grid, (7, 7), 0.1, rng);
+            visited.push((bot.x, bot.y));
+        }
+        visited
+    }
+
+    #[test]
+    fn test_patroller_walks_route_in_order()
+    {
+        let grid = open_room();
+        let mut bot = Bot::with_behaviour(1, 1, Behaviour::Patroller);
+        bot.vision = Vision{range: 1, field_of_view: None};
+        bot.route = vec![(1, 1), (5, 1), (5, 4)];
+        let mut rng = StdRng::seed_from_u64(0);
+        let visited = walk_patrol(&mut bot, &grid, 30, &mut rng);
+        let first_loop: Vec<usize> = bot.route.iter().map(|waypoint| visited.iter().position(|tile| tile == waypoint).unwrap()).collect();
+        assert!(first_loop[1] < first_loop[2] && first_loop[2] < first_loop[0]);
+        // the loop goes on after the last waypoint
+        assert!(visited[first_loop[0] + 1..].contains(&(5, 1)));
+    }
+
+    #[test]
+    fn test_patroller_returns_to_route_after_chase()
+    {
+        let grid = open_room();
+        let mut bot = Bot::with_behaviour(1, 1, Behaviour::Patroller);
+        bot.vision = Vision{range: 3, field_of_view: None};
+        bot.search_duration = 0.5;
+        bot.route = vec![(1, 1), (3, 1)];
+        let mut rng = StdRng::seed_from_u64(0);
+        // the player shows up next to the route and disappears again
+        tick_wanderer(&mut bot, &grid, (1, 3), 0.1, &mut rng);
+        assert_eq!(bot.state, BotState::Chase);
+        let mut steps = 0;
+        while bot.state != BotState::Patrol
+        {
+            tick_wanderer(&mut bot, &grid, (7, 7), 0.1, &mut rng);
+            steps += 1;
+            assert!(steps < 50);
+        }
+        let visited = walk_patrol(&mut bot, &grid, 20, &mut rng);
+        assert!(visited.contains(&(1, 1)) && visited.contains(&(3, 1)));
+        assert!(visited[10..].iter().all(|(x, y)| *y == 1 && *x <= 3));
+    }
+
+    #[test]
+    fn test_patrol_routes_from_settings()
+    {
+        let mut settings = Settings::with_seed(4);
+        settings.bots = vec![BotConfig::new(Behaviour::Patroller), BotConfig::new(Behaviour::Wanderer), BotConfig::new(Behaviour::Patroller)];
+        // (2, 2) is always a wall in the tile map
+        settings.patrol_routes = vec![vec![(1, 1), (2, 2), (1, 3)]];
+        let game = Simulation::new(settings).unwrap();
+        assert_eq!(game.bots[0].route, vec![(1, 1), (1, 3)]);
+        assert!(game.bots[1].route.is_empty());
+        assert!(game.bots[2].route.len() > 1);
+        assert_eq!(game.bots[2].route[0], (game.bots[2].x, game.bots[2].y));
+    }
+
+    #[test]
+    fn test_patroller_without_route_wanders()
+    {
+        let grid = open_room();
+        let bot = Bot::new(4, 4);
+        let mut patroller = Patroller::new();
+        assert!(patroller.next_move(&bot.view(&grid, None, 0.1), &mut StdRng::seed_from_u64(0)).is_some());
+    }
+
+    #[test]
+    fn test_speed_up_has_a_floor()
+    {
+        for difficulty in Difficulty::ALL
+        {
+            let preset = difficulty.preset();
+            let mut bot = Bot::from_config(1, 1, &BotConfig::new(Behaviour::Wanderer), &preset);
+            // a few hours of play
+            for _ in 0..20000
+            {
+                bot.speed_up();
+            }
+            assert_eq!(bot.time_for_step, preset.min_time_for_step, "{:?}", difficulty);
+        }
+
+        let mut fast = Bot::from_config(1, 1, &"wanderer:0.01".parse::<BotConfig>().unwrap(), &Difficulty::Normal.preset());
+        fast.speed_up();
+        assert_eq!(fast.time_for_step, 0.01);
+    }
+
+    #[test]
+    fn test_acceleration_curves()
+    {
+        assert!((Acceleration::Linear(0.01).apply(0.1) - 0.09).abs() < 1e-6);
+        assert!((Acceleration::Exponential(0.5).apply(0.1) - 0.05).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_presets_get_harder()
+    {
+        for pair in Difficulty::ALL.windows(2)
+        {
+            let (easier, harder) = (pair[0].preset(), pair[1].preset());
+            assert!(harder.time_for_step <= easier.time_for_step);
+            assert!(harder.min_time_for_step <= easier.min_time_for_step);
+            assert!(harder.vision_range >= easier.vision_range);
+            assert!(harder.bot_count >= easier.bot_count);
+            assert!(harder.min_time_for_step > 0.0);
+            assert_eq!(pair[0].harder(), pair[1]);
+            assert_eq!(pair[1].easier(), pair[0]);
+        }
+        assert_eq!(Difficulty::Nightmare.harder(), Difficulty::Nightmare);
+        assert_eq!(Difficulty::Easy.easier(), Difficulty::Easy);
+        assert_eq!("NIGHTMARE".parse::<Difficulty>(), Ok(Difficulty::Nightmare));
+        assert!("impossible".parse::<Difficulty>().is_err());
+    }
+
+    #[test]
+    fn test_difficulty_sets_up_bots()
+    {
+        let mut settings = Settings::with_seed(6);
+        settings.difficulty = Difficulty::Hard;
+        let game = Simulation::new(settings.clone()).unwrap();
+        let preset = Difficulty::Hard.preset();
+        assert_eq!(game.bots.len(), preset.bot_count);
+        for bot in &game.bots
+        {
+            assert_eq!(bot.time_for_step, preset.time_for_step);
+            assert_eq!(bot.vision.range, preset.vision_range);
+            assert_eq!(bot.min_time_for_step, preset.min_time_for_step);
+        }
+
+        // more profiles than the preset asks for are all spawned
+        settings.bots = all_profiles();
+        assert_eq!(Simulation::new(settings).unwrap().bots.len(), 4);
+    }
+
+    #[test]
+    fn test_difficulty_picks_the_bot_behaviour()
+    {
+        for (difficulty, behaviour) in [(Difficulty::Easy, Behaviour::RandomWalker), (Difficulty::Normal, Behaviour::Wanderer), (Difficulty::Hard, Behaviour::hunter()), (Difficulty::Nightmare, Behaviour::hunter())]
+        {
+            let mut settings = Settings::with_seed(6);
+            settings.difficulty = difficulty;
+            let game = Simulation::new(settings.clone()).unwrap();
+            assert!(game.bots.iter().all(|bot| bot.behaviour == behaviour), "{:?}", difficulty);
+
+            // profiles set for the level win over the difficulty's
+            settings.bots = vec![BotConfig::new(Behaviour::Guard{radius: 2})];
+            assert!(Simulation::new(settings).unwrap().bots.iter().all(|bot| bot.behaviour == Behaviour::Guard{radius: 2}));
+        }
+    }
+
+    #[test]
+    fn test_select_difficulty_before_start()
+    {
+        let mut game = Simulation::new(Settings::with_seed(6)).unwrap();
+        game.select_difficulty(Difficulty::Nightmare);
+        assert_eq!(game.settings.difficulty, Difficulty::Nightmare);
+        assert_eq!(game.bots.len(), Difficulty::Nightmare.preset().bot_count);
+        assert_eq!(game.game_state, State::Start);
+
+        game.start();
+        game.select_difficulty(Difficulty::Easy);
+        assert_eq!(game.settings.difficulty, Difficulty::Nightmare);
+    }
+
+    // a started game on the given map with the player at (1, 1) and no bots
+    fn simulation_on(rows: &[&str]) -> Simulation
+    {
+        let mut game = new_simulation(1);
+        game.map = grid_from_rows(rows);
+        game.explored = Grid::new(game.map.width(), game.map.height(), false);
+        game.player.update(1, 1);
+        game.bots.clear();
+        game
+    }
+
+    const CORRIDOR: [&str; 3] = [
+        "WWWWWWWWWWWWWWWWWWWWWW",
+        "W....................W",
+        "WWWWWWWWWWWWWWWWWWWWWW"
+    ];
+
+    // holds `direction` for `seconds` at the given frame rate, the first frame is the key press
+    fn hold(game: &mut Simulation, direction: Direction, seconds: f32, fps: u32)
+    {
+        let frames = (seconds * fps as f32).round() as usize;
+        for frame in 0..frames
+        {
+            let input = if frame == 0 { Input::Move(direction) } else { Input::Hold(direction) };
+            game.step(input, 1.0 / fps as f32);
+        }
+    }
+
+    #[test]
+    fn test_holding_key_keeps_player_moving()
+    {
+        for fps in [30, 60, 144]
+        {
+            let mut game = simulation_on(&CORRIDOR);
+            game.player.time_for_step = 0.1;
+            hold(&mut game, Direction::Right, 1.0, fps);
+            // the first step is taken right away, then one every 0.1 seconds
+            assert!((11..=12).contains(&game.player.x), "{} fps: x = {}", fps, game.player.x);
+        }
+    }
+
+    #[test]
+    fn test_key_repeat_does_not_speed_up_player()
+    {
+        let mut game = simulation_on(&CORRIDOR);
+        game.player.time_for_step = 0.1;
+        for _ in 0..60
+        {
+            game.step(Input::Move(Direction::Right), 1.0 / 60.0);
+        }
+        assert!((11..=12).contains(&game.player.x));
+    }
+
+    #[test]
+    fn test_player_stops_on_release()
+    {
+        let mut game = simulation_on(&CORRIDOR);
+        hold(&mut game, Direction::Right, 0.3, 60);
+        let stopped_at = game.player.x;
+        for _ in 0..60
+        {
+            game.step(Input::Idle, 1.0 / 60.0);
+        }
+        assert_eq!(game.player.x, stopped_at);
+        assert_eq!(game.player.moving, None);
+    }
+
+    const JUNCTION: [&str; 5] = [
+        "WWWWWWWWWWWW",
+        "W..........W",
+        "WWWWWW.WWWWW",
+        "WWWWWW.WWWWW",
+        "WWWWWWWWWWWW"
+    ];
+
+    #[test]
+    fn test_turn_buffered_before_junction()
+    {
+        let mut game = simulation_on(&JUNCTION);
+        game.player.time_for_step = 0.1;
+        game.player.update(3, 1);
+        game.player.moving = Some(Direction::Right);
+        // down is still a wall here, the turn is taken two tiles later at the junction
+        hold(&mut game, Direction::Down, 0.5, 60);
+        assert_eq!((game.player.x, game.player.y), (6, 3));
+    }
+
+    #[test]
+    fn test_buffered_turn_expires()
+    {
+        let mut game = simulation_on(&JUNCTION);
+        game.player.time_for_step = 0.1;
+        game.player.update(1, 1);
+        game.step(Input::Move(Direction::Right), 1.0 / 60.0);
+        // a quick tap on down long before the junction, right stays held
+        game.step(Input::Move(Direction::Down), 1.0 / 60.0);
+        for _ in 0..60
+        {
+            game.step(Input::Hold(Direction::Right), 1.0 / 60.0);
+        }
+        assert_eq!(game.player.y, 1);
+        assert!(game.player.x > 6);
+    }
+
+    #[test]
+    fn test_player_and_bot_move_at_the_same_rate()
+    {
+        let mut game = simulation_on(&[
+            "WWWWWWWWWWWWWWWWWWWWWW",
+            "W....................W",
+            "WWWWWWWWWWWWWWWWWWWWWW",
+            "W....................W",
+            "WWWWWWWWWWWWWWWWWWWWWW"
+        ]);
+        let mut bot = Bot::new(1, 3);
+        bot.brain = Box::new(RightWalker);
+        bot.time_for_step = 0.1;
+        bot.time_until_next_step = 0.0;
+        bot.min_time_for_step = 0.1;
+        game.bots.push(bot);
+        game.player.time_for_step = 0.1;
+        hold(&mut game, Direction::Right, 1.5, 60);
+        assert_eq!(game.player.x, game.bots[0].x);
+    }
+
+    #[test]
+    fn test_step_progress()
+    {
+        assert_eq!(animation::step_progress(0.1, 0.1), 0.0);
+        assert!((animation::step_progress(0.025, 0.1) - 0.75).abs() < 1e-6);
+        assert_eq!(animation::step_progress(-0.02, 0.1), 1.0);
+        assert_eq!(animation::step_progress(0.0, 0.0), 1.0);
+    }
+
+    #[test]
+    fn test_pose_slides_between_cells()
+    {
+        let pose = animation::pose((2, 3), (3, 3), 0.5, 4);
+        assert_eq!((pose.x, pose.y), (2.5, 3.0));
+        assert!(pose.bob > 0.0);
+
+        let standing = animation::pose((3, 3), (3, 3), 0.5, 4);
+        assert_eq!((standing.x, standing.y, standing.frame, standing.bob), (3.0, 3.0, 0, 0.0));
+        let arrived = animation::pose((2, 3), (3, 3), 1.0, 4);
+        assert_eq!((arrived.x, arrived.frame), (3.0, 0));
+    }
+
+    #[test]
+    fn test_walk_cycle_runs_over_two_steps()
+    {
+        let mut frames = Vec::new();
+        for (previous, current) in [((2, 1), (3, 1)), ((3, 1), (4, 1))]
+        {
+            for tenth in 0..10
+            {
+                frames.push(animation::pose(previous, current, tenth as f32 / 10.0, 4).frame);
+            }
+        }
+        // every frame shows up, in order, without jumping back in between
+        let mut expected = frames.clone();
+        expected.sort();
+        assert_eq!(frames, expected);
+        assert_eq!(frames.first(), Some(&0));
+        assert_eq!(frames.last(), Some(&3));
+        // a single sprite has nothing to cycle through
+        assert_eq!(animation::pose((1, 1), (2, 1), 0.5, 1).frame, 0);
+    }
+
+    #[test]
+    fn test_walk_cycles_have_frames_for_every_direction()
+    {
+        // ggez paths start at the resources folder
+        let exists = |path: &str| std::path::Path::new("resources").join(path.trim_start_matches('\\')).exists();
+        for sprite in assets::WALKING_SPRITES
+        {
+            assert!(exists(sprite));
+            assert!(!assets::walk_frame_paths(sprite, exists).is_empty(), "{} has no walk frames", sprite);
+        }
+        assert!(assets::walk_frame_paths("\\missing.png", exists).is_empty());
+    }
+
+    #[test]
+    fn test_steps_remember_previous_cell()
+    {
+        let mut game = simulation_on(&CORRIDOR);
+        let mut bot = Bot::new(10, 1);
+        bot.brain = Box::new(RightWalker);
+        bot.time_until_next_step = 0.0;
+        game.bots.push(bot);
+        game.step(Input::Move(Direction::Right), 1.0 / 60.0);
+        assert_eq!(game.player.previous, (1, 1));
+        assert_eq!((game.player.x, game.player.y), (2, 1));
+        assert_eq!(game.bots[0].previous, (10, 1));
+        assert_eq!(game.bots[0].x, 11);
+    }
+
+    #[test]
+    fn test_camera_stays_inside_the_world()
+    {
+        let mut camera = Camera::new(400.0, 300.0);
+        let world = (1000.0, 800.0);
+        camera.jump_to((0.0, 0.0), world);
+        assert_eq!(camera.view_rect(), (0.0, 0.0, 400.0, 300.0));
+        camera.jump_to((1000.0, 800.0), world);
+        assert_eq!(camera.view_rect(), (600.0, 500.0, 400.0, 300.0));
+        camera.jump_to((500.0, 400.0), world);
+        assert_eq!((camera.x, camera.y), (500.0, 400.0));
+    }
+
+    #[test]
+    fn test_camera_centres_small_world()
+    {
+        let mut camera = Camera::new(400.0, 300.0);
+        camera.jump_to((10.0, 290.0), (200.0, 1000.0));
+        assert_eq!(camera.x, 100.0);
+        assert_eq!(camera.y, 290.0);
+        // zoomed out far enough the whole world fits and sits in the middle of the window
+        camera.set_zoom(0.25, (200.0, 1000.0));
+        assert_eq!((camera.x, camera.y), (100.0, 500.0));
+    }
+
+    #[test]
+    fn test_camera_zoom_limits()
+    {
+        let mut camera = Camera::new(400.0, 300.0);
+        let world = (1000.0, 800.0);
+        camera.zoom_by(100.0, world);
+        assert_eq!(camera.zoom, camera.max_zoom);
+        camera.zoom_by(0.001, world);
+        assert_eq!(camera.zoom, camera.min_zoom);
+        camera.set_zoom(2.0, world);
+        assert_eq!(camera.view_size(), (200.0, 150.0));
+    }
+
+    #[test]
+    fn test_camera_eases_towards_target()
+    {
+        let mut camera = Camera::new(400.0, 300.0);
+        let world = (1000.0, 800.0);
+        camera.jump_to((200.0, 150.0), world);
+        camera.follow((600.0, 150.0), world, 1.0 / 60.0);
+        assert!(camera.x > 200.0 && camera.x < 600.0);
+        let mut last = camera.x;
+        for _ in 0..120
+        {
+            camera.follow((600.0, 150.0), world, 1.0 / 60.0);
+            assert!(camera.x >= last);
+            last = camera.x;
+        }
+        assert!((camera.x - 600.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_camera_visible_cells()
+    {
+        let mut camera = Camera::new(90.0, 90.0);
+        let world = (450.0, 450.0);
+        camera.jump_to((45.0, 45.0), world);
+        assert_eq!(camera.visible_cells(45.0, 10, 10), (0..2, 0..2));
+        // half a tile in, the tiles on both edges are partly visible
+        camera.jump_to((100.0, 67.5), world);
+        assert_eq!(camera.visible_cells(45.0, 10, 10), (1..4, 0..3));
+        camera.jump_to((450.0, 450.0), world);
+        assert_eq!(camera.visible_cells(45.0, 10, 10), (8..10, 8..10));
+    }
+
+    const ROOMS: [&str; 5] = [
+        "WWWWWWWWWWWWWWWWWWWWWW",
+        "W.....W..............W",
+        "W.....W.............KW",
+        "W....................W",
+        "WWWWWWWWWWWWWWWWWWWWWW"
+    ];
+
+    #[test]
+    fn test_player_explores_what_they_see()
+    {
+        let mut game = simulation_on(&ROOMS);
+        game.reveal();
+        assert!(game.is_explored(1, 1));
+        // the wall that blocks the view is seen, what is behind it is not
+        assert!(game.is_explored(6, 1));
+        assert!(!game.is_explored(7, 1));
+        assert!(!game.is_explored(20, 2));
+
+        hold(&mut game, Direction::Down, 0.3, 60);
+        hold(&mut game, Direction::Right, 1.5, 60);
+        assert!(game.player.x > 10);
+        assert!(game.is_explored(7, 1));
+        // explored tiles stay explored after the player walked away
+        assert!(game.is_explored(1, 1));
+    }
+
+    #[test]
+    fn test_minimap_shows_key_once_discovered()
+    {
+        let mut game = simulation_on(&ROOMS);
+        game.reveal();
+        let has_key = |game: &Simulation| minimap::explored_tiles(game).any(|(_, tile)| tile == Tile::Key);
+        assert!(!has_key(&game));
+        assert!(minimap::explored_tiles(&game).all(|((x, y), _)| game.is_explored(x, y)));
+        game.player.update(17, 2);
+        game.reveal();
+        assert!(has_key(&game));
+    }
+
+    #[test]
+    fn test_minimap_layout()
+    {
+        let window = (800.0, 600.0);
+        let mut map = Minimap{size: 200.0, margin: 10.0, ..Minimap::default()};
+        // the longer side of the maze fills the size, the other one keeps its proportions
+        map.corner = Corner::TopRight;
+        assert_eq!(map.layout(window, 40, 20), (590.0, 10.0, 5.0));
+        map.corner = Corner::BottomLeft;
+        assert_eq!(map.layout(window, 40, 20), (10.0, 490.0, 5.0));
+        map.corner = Corner::BottomRight;
+        assert_eq!(map.layout(window, 20, 40), (690.0, 390.0, 5.0));
+    }
+
+    #[test]
+    fn test_parse_minimap()
+    {
+        let map = "bottom-left:240".parse::<Minimap>().unwrap();
+        assert_eq!((map.corner, map.size), (Corner::BottomLeft, 240.0));
+        assert_eq!("Top_Right".parse::<Minimap>().unwrap().corner, Corner::TopRight);
+        assert!("middle".parse::<Minimap>().is_err());
+        assert!("top-left:-5".parse::<Minimap>().is_err());
+    }
+
+    #[test]
+    fn test_sight_radius_ignores_walls()
+    {
+        let grid = grid_from_rows(&ROOMS);
+        let radius = visibility::visible_tiles(&grid, (5, 1), Sight::Radius(3));
+        let line_of_sight = visibility::visible_tiles(&grid, (5, 1), Sight::LineOfSight(3));
+        assert!(radius[(7, 1)]);
+        assert!(!line_of_sight[(7, 1)]);
+        for sight in [radius, line_of_sight]
+        {
+            assert!(sight[(5, 1)] && sight[(6, 1)]);
+            // out of range, even straight down an open corridor
+            assert!(!sight[(1, 3)]);
+            assert!(!sight[(9, 1)]);
+        }
+    }
+
+    #[test]
+    fn test_parse_sight()
+    {
+        assert_eq!("radius:3".parse::<Sight>(), Ok(Sight::Radius(3)));
+        assert_eq!("sight".parse::<Sight>(), Ok(Sight::LineOfSight(8)));
+        assert!("radius:0".parse::<Sight>().is_err());
+        assert!("xray".parse::<Sight>().is_err());
+    }
+
+    #[test]
+    fn test_fog_hides_what_is_out_of_sight()
+    {
+        let mut game = simulation_on(&ROOMS);
+        game.reveal();
+        assert!(game.is_visible(20, 2));
+
+        game.settings.fog = Some(Sight::LineOfSight(4));
+        game.reveal();
+        assert!(game.is_visible(1, 1) && game.is_visible(4, 3));
+        assert!(!game.is_visible(7, 1));
+        assert!(!game.is_visible(20, 2));
+
+        // walking away keeps the tiles explored, but they are no longer in view
+        hold(&mut game, Direction::Down, 0.3, 60);
+        hold(&mut game, Direction::Right, 1.5, 60);
+        assert!(game.is_explored(1, 1));
+        assert!(!game.is_visible(1, 1));
+        assert!(game.is_visible(game.player.x, game.player.y));
+    }
+
+    #[test]
+    fn test_fog_hides_bots()
+    {
+        let mut game = simulation_on(&ROOMS);
+        game.bots = vec![Bot::new(4, 3), Bot::new(20, 2)];
+        game.reveal();
+        assert_eq!(game.visible_bots().count(), 2);
+
+        game.settings.fog = Some(Sight::LineOfSight(4));
+        game.reveal();
+        let visible: Vec<(usize, usize)> = game.visible_bots().map(|bot| (bot.x, bot.y)).collect();
+        assert_eq!(visible, vec![(4, 3)]);
+    }
+
+    fn locked_level(seed: u64, locked_doors: usize) -> Simulation
+    {
+        let mut settings = Settings::with_seed(seed);
+        settings.width = 12;
+        settings.height = 12;
+        settings.locked_doors = locked_doors;
+        Simulation::new(settings).unwrap()
+    }
+
+    fn tiles_matching(grid: &Grid<Tile>, matches: impl Fn(Tile) -> bool) -> Vec<(usize, usize)>
+    {
+        grid.positions().filter(|position| matches(grid[*position])).collect()
+    }
+
+    #[test]
+    fn test_locked_doors_can_all_be_opened()
+    {
+        for seed in 0..20
+        {
+            let game = locked_level(seed, 3);
+            let start = (game.player.x, game.player.y);
+            let doors = tiles_matching(&game.map, |tile| matches!(tile, Tile::LockedDoor(_)));
+            assert_eq!(doors.len(), 3, "seed {}", seed);
+            assert_eq!(tiles_matching(&game.map, |tile| matches!(tile, Tile::ColouredKey(_))).len(), 3);
+            assert!(keys::can_collect_all(&game.map, start), "seed {}", seed);
+            // the plain key is still somewhere in the maze
+            assert_eq!(tiles_matching(&game.map, |tile| tile == Tile::Key).len(), 1);
+        }
+    }
+
+    #[test]
+    fn test_no_key_behind_its_own_door()
+    {
+        for seed in 0..20
+        {
+            let game = locked_level(seed, 4);
+            let start = (game.player.x, game.player.y);
+            for colour in Colour::ALL
+            {
+                // every other door open, only the one of this colour in the way
+                let mut map = game.map.clone();
+                for (x, y) in tiles_matching(&game.map, |tile| matches!(tile, Tile::LockedDoor(other) if other != colour))
+                {
+                    map.set(x, y, Tile::Floor);
+                }
+                let reachable = pathfinding::distances(&map, start);
+                for key in tiles_matching(&map, |tile| tile == Tile::ColouredKey(colour))
+                {
+                    assert!(reachable[key].is_some(), "seed {} {:?}", seed, colour);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_no_locked_doors_by_default()
+    {
+        let game = locked_level(3, 0);
+        assert!(tiles_matching(&game.map, |tile| matches!(tile, Tile::LockedDoor(_) | Tile::ColouredKey(_))).is_empty());
+        assert_eq!(game.map, locked_level(3, 0).map);
+    }
+
+    #[test]
+    fn test_collect_order_detects_key_behind_its_door()
+    {
+        let grid = grid_from_rows(&["WWWWWWW", "W..r.1W", "WWWWWWW"]);
+        assert!(!keys::can_collect_all(&grid, (1, 1)));
+        let grid = grid_from_rows(&["WWWWWWW", "W.1r3bW", "WWWWWWW"]);
+        assert!(keys::can_collect_all(&grid, (1, 1)));
+    }
+
+    #[test]
+    fn test_locked_door_needs_its_key()
+    {
+        let mut game = simulation_on(&["WWWWWWWWW", "W..3r1b.W", "WWWWWWWWW"]);
+        assert!(!game.can_enter(4, 1));
+        hold(&mut game, Direction::Right, 0.5, 60);
+        // the blue key does not open the red door
+        assert_eq!((game.player.x, game.player.y), (3, 1));
+        assert!(game.player.keys.contains(Colour::Blue));
+        assert_eq!(game.map[(4, 1)], Tile::LockedDoor(Colour::Red));
+
+        game.player.keys.insert(Colour::Red);
+        hold(&mut game, Direction::Right, 1.0, 60);
+        assert_eq!((game.player.x, game.player.y), (7, 1));
+        assert_eq!(game.map[(4, 1)], Tile::Floor);
+        assert_eq!(game.map[(6, 1)], Tile::Floor);
+        assert_eq!(game.player.keys.iter().collect::<Vec<_>>(), vec![Colour::Red, Colour::Blue]);
+    }
+
+    #[test]
+    fn test_locked_door_hides_the_player()
+    {
+        let mut grid = grid_from_rows(&["WWWWWWWWW", "W...r...W", "WWWWWWWWW"]);
+        let bot = Bot::new(1, 1);
+        assert!(!bot.can_see(&grid, (7, 1)));
+        // the door itself is in view, and so is everything behind it once it is open
+        assert!(bot.can_see(&grid, (4, 1)));
+        grid.set(4, 1, Tile::Floor);
+        assert!(bot.can_see(&grid, (7, 1)));
+        // the exit never hides anything
+        grid.set(4, 1, Tile::Door);
+        assert!(bot.can_see(&grid, (7, 1)));
+    }
+
+    #[test]
+    fn test_bots_stop_at_locked_doors()
+    {
+        let mut game = simulation_on(&["WWWWWWWW", "W...g..W", "WWWWWWWW"]);
+        game.bots.push(Bot::new(5, 1));
+        game.update_bot_position(0, 4, 1);
+        assert_eq!((game.bots[0].x, game.bots[0].y), (5, 1));
+        assert!(pathfinding::find_path(&game.map, (5, 1), None, (1, 1), 0).is_none());
+    }
+
+    #[test]
+    fn test_exit_is_farthest_dead_end()
+    {
+        for seed in 0..10
+        {
+            let game = new_simulation(seed);
+            let distances = pathfinding::distances(&game.map, (1, 1));
+            assert_eq!(game.map[game.exit], Tile::Door);
+            assert_eq!(game.map.walkable_neighbors(game.exit.0, game.exit.1).count(), 1);
+            let exit_distance = distances[game.exit].unwrap();
+            assert!(game.map.dead_ends().all(|dead_end| distances[dead_end].unwrap() <= exit_distance), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn test_placement_keeps_its_distance()
+    {
+        for seed in 0..20
+        {
+            let mut settings = Settings::with_seed(seed);
+            settings.difficulty = Difficulty::Nightmare;
+            let game = Simulation::new(settings).unwrap();
+            let distances = pathfinding::distances(&game.map, (1, 1));
+            let farthest = game.map.positions().filter_map(|position| distances[position]).max().unwrap();
+            assert_eq!(game.bots.len(), 3);
+            for bot in &game.bots
+            {
+                assert!(distances[(bot.x, bot.y)].unwrap() >= 10, "seed {}", seed);
+            }
+            let key = game.map.positions().find(|position| game.map[*position] == Tile::Key).unwrap();
+            assert!(distances[key].unwrap() >= farthest / 2, "seed {}", seed);
+            assert!(game.is_solvable());
+        }
+    }
+
+    #[test]
+    fn test_small_maze_still_places_everything()
+    {
+        let mut settings = Settings::with_seed(4);
+        settings.width = 2;
+        settings.height = 2;
+        settings.difficulty = Difficulty::Nightmare;
+        let game = Simulation::new(settings).unwrap();
+        assert_eq!(game.bots.len(), 3);
+        assert!(game.bots.iter().all(|bot| (bot.x, bot.y) != (1, 1)));
+        assert!(game.is_solvable());
+    }
+
+    #[test]
+    fn test_maze_without_room_for_the_key_is_an_error()
+    {
+        let mut settings = Settings::with_seed(4);
+        settings.width = 1;
+        settings.height = 1;
+        assert!(Simulation::new(settings).is_err());
+    }
+
+    #[test]
+    fn test_walled_off_exit_is_not_solvable()
+    {
+        let mut game = simulation_on(&["WWWWWWW", "W.K.WVW", "WWWWWWW"]);
+        game.exit = (5, 1);
+        assert!(!game.is_solvable());
+        game.map.set(4, 1, Tile::LockedDoor(Colour::Green));
+        assert!(!game.is_solvable());
+        game.map.set(3, 1, Tile::ColouredKey(Colour::Green));
+        assert!(game.is_solvable());
+    }
+
+    #[test]
+    fn test_lock_doors_reports_an_unsolvable_maze()
+    {
+        let mut rng = StdRng::seed_from_u64(0);
+        let corridor = grid_from_rows(&["WWWWWWWWWWWWW", "W..........VW", "W.WWWWWWWWWWW", "W.K.........W", "WWWWWWWWWWWWW"]);
+        let locked = simulation::lock_doors(&corridor, (1, 1), (11, 1), 2, &[(1, 1), (3, 3)], &mut rng).unwrap();
+        assert!(locked.iter().any(|(_, tile)| matches!(tile, Tile::LockedDoor(_))));
+        assert!(keys::can_collect_all(&locked, (1, 1)));
+
+        // every try fails when the exit is walled off, and so does the level without doors
+        let walled_off = grid_from_rows(&["WWWWWWWWWWWWW", "W.........WVW", "W.WWWWWWWWWWW", "W.K.........W", "WWWWWWWWWWWWW"]);
+        assert!(simulation::lock_doors(&walled_off, (1, 1), (11, 1), 2, &[(1, 1), (3, 3)], &mut rng).is_err());
+    }
+
+    // every step of the path is to a walkable neighbour, from the start to the goal
+    fn assert_valid_path(grid: &Grid<Tile>, solution: &Solution, start: (usize, usize), goal: (usize, usize))
+    {
+        let path = solution.path.as_ref().unwrap();
+        assert_eq!(path.first(), Some(&start));
+        assert_eq!(path.last(), Some(&goal));
+        assert!(path.iter().all(|(x, y)| grid.is_walkable(*x, *y)));
+        assert!(path.windows(2).all(|step| step[0].0.abs_diff(step[1].0) + step[0].1.abs_diff(step[1].1) == 1));
+        assert_eq!(solution.trace.first(), Some(&start));
+        assert!(solution.visited <= solution.trace.len());
+    }
+
+    fn maze_grid(seed: u64, algorithm: Algorithm, loop_density: f64) -> Grid<Tile>
+    {
+        let mut graph = Graph::with_seed(8, 6, seed);
+        algorithm.generate(&mut graph);
+        graph.braid(loop_density);
+        Grid::from_chars(&graph.draw_maze())
+    }
+
+    #[test]
+    fn test_every_maze_can_be_solved()
+    {
+        for algorithm in Algorithm::ALL
+        {
+            for seed in 0..3
+            {
+                let game = new_simulation(seed + 100 * algorithm as u64);
+                let key = game.map.positions().find(|position| game.map[*position] == Tile::Key).unwrap();
+                for solver in Solver::ALL.into_iter().filter(|solver| solver.is_optimal())
+                {
+                    let to_key = solver.solve(&game.map, (1, 1), key);
+                    let to_exit = solver.solve(&game.map, key, game.exit);
+                    assert_valid_path(&game.map, &to_key, (1, 1), key);
+                    assert_valid_path(&game.map, &to_exit, key, game.exit);
+                }
+            }
+            // without loops every solver gets there, the wall follower included
+            let grid = maze_grid(7, algorithm, 0.0);
+            let goal = (grid.width() - 2, grid.height() - 2);
+            for solver in Solver::ALL
+            {
+                assert_valid_path(&grid, &solver.solve(&grid, (1, 1), goal), (1, 1), goal);
+            }
+        }
+    }
+
+    #[test]
+    fn test_shortest_path_solvers_agree()
+    {
+        for seed in 0..10
+        {
+            let grid = maze_grid(seed, Algorithm::default(), 0.5);
+            let goal = (grid.width() - 2, grid.height() - 2);
+            let breadth_first = Solver::BreadthFirst.solve(&grid, (1, 1), goal);
+            let shortest = breadth_first.length().unwrap();
+            assert_eq!(Solver::Dijkstra.solve(&grid, (1, 1), goal).length(), Some(shortest));
+            let a_star = Solver::AStar.solve(&grid, (1, 1), goal);
+            assert_eq!(a_star.length(), Some(shortest));
+            // the estimate keeps A* from looking at more of the maze than a blind search
+            assert!(a_star.visited <= breadth_first.visited);
+            let tremaux = Solver::Tremaux.solve(&grid, (1, 1), goal);
+            assert_valid_path(&grid, &tremaux, (1, 1), goal);
+            assert!(tremaux.length().unwrap() >= shortest);
+        }
+    }
+
+    #[test]
+    fn test_tremaux_walks_every_passage_at_most_twice()
+    {
+        for seed in 0..10
+        {
+            let grid = maze_grid(seed, Algorithm::Prim, 1.0);
+            let goal = (grid.width() - 2, grid.height() - 2);
+            let solution = Solver::Tremaux.solve(&grid, (1, 1), goal);
+            assert_valid_path(&grid, &solution, (1, 1), goal);
+            let mut walked: Vec<((usize, usize), (usize, usize))> = solution.trace.windows(2).map(|step| if step[0] < step[1] { (step[0], step[1]) } else { (step[1], step[0]) }).collect();
+            walked.sort();
+            assert!(walked.windows(3).all(|passages| !(passages[0] == passages[1] && passages[1] == passages[2])), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn test_solvers_report_unreachable_goal()
+    {
+        let grid = grid_from_rows(&["WWWWWWW", "W..W..W", "W..W..W", "WWWWWWW"]);
+        for solver in Solver::ALL
+        {
+            let solution = solver.solve(&grid, (1, 1), (5, 2));
+            assert!(!solution.is_solved(), "{:?}", solver);
+            assert!(solution.visited <= 4);
+        }
+        assert!(!Solver::AStar.solve(&grid, (1, 1), (3, 1)).is_solved());
+    }
+
+    #[test]
+    fn test_wall_follower_gives_up_on_an_island()
+    {
+        // the goal in the middle of a loop, a hand on the outer wall never gets there
+        let grid = grid_from_rows(&["WWWWWWW", "W.....W", "W.W.W.W", "W.....W", "WWWWWWW"]);
+        assert!(!Solver::WallFollower.solve(&grid, (1, 1), (3, 2)).is_solved());
+        assert!(Solver::Tremaux.solve(&grid, (1, 1), (3, 2)).is_solved());
+    }
+
+    #[test]
+    fn test_parse_solver()
+    {
+        assert_eq!("a-star".parse::<Solver>(), Ok(Solver::AStar));
+        assert_eq!("BFS".parse::<Solver>(), Ok(Solver::BreadthFirst));
+        assert_eq!("wall_follower".parse::<Solver>(), Ok(Solver::WallFollower));
+        assert!("guess".parse::<Solver>().is_err());
+    }
+
+    #[test]
+    fn test_hint_shows_way_to_key_then_exit()
+    {
+        let mut game = simulation_on(&["WWWWWWWWW", "W.....K.W", "WWWWWWW.W", "WV......W", "WWWWWWWWW"]);
+        game.exit = (1, 3);
+        assert!(game.request_hint());
+        let hint = game.hint.clone().unwrap();
+        assert_eq!(hint.path, vec![(1, 1), (2, 1), (3, 1), (4, 1), (5, 1), (6, 1)]);
+        assert_eq!(hint.time_left, simulation::HINT_DURATION);
+
+        game.player.has_key = true;
+        game.time_until_hint = 0.0;
+        assert!(game.request_hint());
+        let path = game.hint.clone().unwrap().path;
+        assert_eq!(path.first(), Some(&(1, 1)));
+        assert_eq!(path.last(), Some(&(1, 3)));
+        assert_eq!(path.len(), 15);
+    }
+
+    #[test]
+    fn test_hints_are_limited_and_cost_points()
+    {
+        let mut game = simulation_on(&["WWWWWWW", "W....KW", "WWWWWWW"]);
+        game.hints_left = 2;
+        assert!(game.request_hint());
+        assert_eq!(game.penalty, simulation::HINT_COST);
+        // still cooling down
+        assert!(!game.request_hint());
+        game.step(Input::Idle, simulation::HINT_COOLDOWN);
+        assert!(game.request_hint());
+        game.step(Input::Idle, simulation::HINT_COOLDOWN);
+        assert!(!game.request_hint());
+        assert_eq!(game.hints_left, 0);
+        assert_eq!(game.penalty, 2 * simulation::HINT_COST);
+    }
+
+    #[test]
+    fn test_hint_fades_after_a_while()
+    {
+        let mut game = simulation_on(&["WWWWWWW", "W....KW", "WWWWWWW"]);
+        assert!(game.request_hint());
+        game.step(Input::Idle, simulation::HINT_DURATION / 2.0);
+        assert!(game.hint.is_some());
+        game.step(Input::Idle, simulation::HINT_DURATION / 2.0);
+        assert!(game.hint.is_none());
+    }
+
+    #[test]
+    fn test_hint_stays_in_the_explored_part_under_fog()
+    {
+        let mut game = simulation_on(&["WWWWWWWWWWWWW", "W.........K.W", "WWWWWWWWWWWWW"]);
+        assert!(game.request_hint());
+        assert_eq!(game.hint_tiles().count(), 10);
+
+        game.settings.fog = Some(Sight::LineOfSight(3));
+        game.reveal();
+        let shown: Vec<(usize, usize)> = game.hint_tiles().collect();
+        assert_eq!(shown.first(), Some(&(1, 1)));
+        assert!(shown.len() < 10);
+        assert!(shown.iter().all(|(x, y)| game.is_explored(*x, *y)));
+    }
+
+    #[test]
+    fn test_hint_leads_to_key_for_locked_door()
+    {
+        let mut game = simulation_on(&["WWWWWWWWW", "W..r..K.W", "W.WWWWWWW", "W...1...W", "WWWWWWWWW"]);
+        assert!(game.request_hint());
+        assert_eq!(game.hint.clone().unwrap().path.last(), Some(&(4, 3)));
+        // with the key the door is no longer in the way
+        game.player.keys.insert(Colour::Red);
+        game.time_until_hint = 0.0;
+        assert!(game.request_hint());
+        assert_eq!(game.hint.clone().unwrap().path, vec![(1, 1), (2, 1), (3, 1), (4, 1), (5, 1), (6, 1)]);
+    }
+
+    #[test]
+    fn test_no_hint_before_the_game_starts()
+    {
+        let mut game = Simulation::new(Settings::with_seed(2)).unwrap();
+        assert!(!game.request_hint());
+        assert_eq!(game.hints_left, 3);
+        assert_eq!(game.penalty, 0);
+    }
+
+    #[test]
+    fn test_stats_count_time_steps_and_key()
+    {
+        let mut game = simulation_on(&["WWWWWWWW", "W...K..W", "WWWWWWWW"]);
+        hold(&mut game, Direction::Right, 1.0, 60);
+        assert!((game.stats.elapsed - 1.0).abs() < 0.001);
+        assert_eq!(game.stats.steps, 5);
+        // the key is three steps away, the first one right on the key press
+        let key_time = game.stats.key_time.unwrap();
+        assert!(key_time > 0.1 && key_time < 0.3, "{}", key_time);
+
+        // nothing counts once the game is over
+        game.game_state = State::Credits(String::from("Game Over!"));
+        game.step(Input::Hold(Direction::Left), 1.0);
+        assert!((game.stats.elapsed - 1.0).abs() < 0.001);
+        assert_eq!(game.stats.steps, 5);
+    }
+
+    #[test]
+    fn test_score_for_key_and_escape()
+    {
+        let mut game = simulation_on(&["WWWWWWW", "W.K..VW", "WWWWWWW"]);
+        game.exit = (5, 1);
+        assert_eq!(game.score(), 0);
+        hold(&mut game, Direction::Right, 1.0, 60);
+        assert!(game.stats.escaped);
+        assert_eq!(game.game_state, State::Credits(String::from("You found the exit!")));
+        // 10x10 cells on normal: 1000 points, 1.5 times, and a second of the 50 second par time was used
+        let time_bonus = (50.0 - game.stats.elapsed) * 10.0;
+        assert_eq!(game.score(), ((1000.0 + time_bonus) * 1.5_f32).round() as u32);
+
+        game.penalty = 100;
+        assert_eq!(game.score(), ((1000.0 + time_bonus) * 1.5_f32).round() as u32 - 100);
+        game.penalty = 100_000;
+        assert_eq!(game.score(), 0);
+    }
+
+    #[test]
+    fn test_score_grows_with_size_and_difficulty()
+    {
+        let score = |width: usize, difficulty: Difficulty|
+        {
+            let mut settings = Settings::with_seed(1);
+            settings.width = width;
+            settings.difficulty = difficulty;
+            let mut game = Simulation::new(settings).unwrap();
+            game.player.has_key = true;
+            game.stats.escaped = true;
+            game.stats.elapsed = 30.0;
+            game.score()
+        };
+        assert!(score(20, Difficulty::Normal) > score(10, Difficulty::Normal));
+        let mut last = 0;
+        for difficulty in Difficulty::ALL
+        {
+            assert!(score(10, difficulty) > last);
+            last = score(10, difficulty);
+        }
+    }
+}